@@ -0,0 +1,102 @@
+use std::mem::{align_of, size_of};
+
+use super::*;
+
+//Bump this whenever the fields of `Api` or the signatures they point to change.
+pub const API_VERSION: u32 = 1;
+
+//The only symbol `main` looks up by name. Everything else is reached through
+//the returned `Api`.
+pub const API_SYMBOL: &'static [u8] = b"state_manipulation_api\0";
+
+pub type GetApi = extern "C" fn() -> *const Api;
+
+//`State` is deliberately only ever passed behind a pointer, (see
+//`common::save`,) so its layout is not part of the hash and can change across
+//reloads. Everything else here is read by both sides, so it is.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Api {
+    pub version: u32,
+    pub abi_hash: u64,
+    pub new_state: fn(Size) -> Box<State>,
+    pub update_and_render: fn(&Platform, &mut State, &mut Vec<Event>) -> bool,
+    pub save_state: fn(Box<State>) -> Vec<u8>,
+    pub load_state: fn(Size, &[u8]) -> Box<State>,
+    pub drop_state: fn(Box<State>),
+}
+
+//Both sides hash the source of everything they share, so any change to those
+//definitions, even just adding or reordering a variant or a field, means a
+//library built against the old ones gets refused instead of misread. `Api`
+//itself is defined in this file, and `State` is in neither, (see above.)
+const SHARED_SOURCES: [&'static str; 2] = [include_str!("abi.rs"), include_str!("platform.rs")];
+
+//FNV-1a, since all we need is for both sides to compute the same thing.
+//These are `const` so the library can put its `Api` in a `static`.
+const fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+
+    hash
+}
+
+const fn hash_usize(hash: u64, n: usize) -> u64 {
+    hash_bytes(hash, &(n as u64).to_le_bytes())
+}
+
+macro_rules! hash_layouts {
+    ($hash: expr, $($t: ty),*) => {{
+        let mut hash = $hash;
+        $(
+            hash = hash_usize(hash, size_of::<$t>());
+            hash = hash_usize(hash, align_of::<$t>());
+        )*
+        hash
+    }}
+}
+
+pub const fn abi_hash() -> u64 {
+    let mut hash = 0xcbf29ce484222325;
+
+    let mut i = 0;
+    while i < SHARED_SOURCES.len() {
+        hash = hash_bytes(hash, SHARED_SOURCES[i].as_bytes());
+        i += 1;
+    }
+
+    let hash = hash_usize(hash, API_VERSION as usize);
+
+    hash_layouts!(hash,
+                  Api,
+                  Platform,
+                  Event,
+                  KeyCode,
+                  Point,
+                  Size,
+                  Rect,
+                  Color,
+                  Vec<Event>,
+                  Vec<u8>,
+                  &[u8],
+                  Box<State>)
+}
+
+//Returns a description of what is wrong with `api`, if anything.
+pub fn check(api: &Api) -> Result<(), String> {
+    if api.version != API_VERSION {
+        Err(format!("library has API version {}, expected {}",
+                    api.version,
+                    API_VERSION))
+    } else if api.abi_hash != abi_hash() {
+        Err(format!("library has ABI hash {:x}, expected {:x}",
+                    api.abi_hash,
+                    abi_hash()))
+    } else {
+        Ok(())
+    }
+}
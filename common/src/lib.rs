@@ -6,44 +6,388 @@ use rand::StdRng;
 use std::cmp::Ordering;
 use std::cmp::Ordering::{Less, Equal, Greater};
 
-pub struct Platform {
-    pub print_xy: fn(i32, i32, &str),
-    pub clear: fn(Option<Rect>),
-    pub size: fn() -> Size,
-    pub pick: fn(Point, i32) -> char,
-    pub mouse_position: fn() -> Point,
-    pub clicks: fn() -> i32,
-    pub key_pressed: fn(KeyCode) -> bool,
-    pub set_colors: fn(Color, Color),
-    pub get_colors: fn() -> (Color, Color),
-    pub set_foreground: fn(Color),
-    pub get_foreground: fn() -> (Color),
-    pub set_background: fn(Color),
-    pub get_background: fn() -> (Color),
-    pub set_layer: fn(i32),
-    pub get_layer: fn() -> i32,
-}
+pub mod abi;
+pub mod odds;
+mod platform;
+pub mod save;
+
+pub use platform::*;
 
 pub struct State {
     pub rng: StdRng,
     pub title_screen: bool,
+    pub mode: Mode,
     pub deck: Vec<Card>,
     pub pile: Vec<Card>,
     pub player: HandEnum,
     pub cpu_players: Vec<HandEnum>,
+    //the first to play sits to their left, and it moves along a seat each deal
+    pub dealer: Participant,
+    //copied from `settings` when the cards are dealt
+    pub rules: Ruleset,
     pub turn: Turn,
     pub turn_count: u32,
+    //how many times the turn has come back around to whoever went first this
+    //deal
+    pub round: u32,
     pub summary: String,
+    pub cpu_last_actions: Vec<String>,
+    pub animations: Vec<Tween>,
+    //worked out during the player's turn, see `odds::Summary`
+    pub odds: Option<odds::Summary>,
+    pub settings: Settings,
+    pub statistics: Statistics,
+    pub dialog: Option<Dialog>,
     pub ui_context: UIContext,
 }
 
+#[derive(Clone, PartialEq, Debug)]
+pub enum Mode {
+    //deal after deal, with nothing carried over
+    Casual,
+    //Everyone starts with `STARTING_LIVES`, and the lowest hand each deal loses
+    //one. These are the lives for you, then each cpu. Empty means the match
+    //hasn't been dealt yet.
+    Match(Vec<u32>),
+    //the cpus play with their cards face up, and nothing counts towards the
+    //statistics
+    Practice,
+    //A scripted first deal. Which step of it the player is on, and whether they
+    //just tried to do something other than what that step asks for.
+    Tutorial(usize, bool),
+}
+
+pub const STARTING_LIVES: u32 = 3;
+
+impl Mode {
+    pub fn lives(&self, participant: &Participant) -> Option<u32> {
+        match *self {
+            Mode::Match(ref lives) => {
+                let index = match *participant {
+                    Player => 0,
+                    Cpu(i) => i + 1,
+                };
+
+                lives.get(index).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_out(&self, participant: &Participant) -> bool {
+        self.lives(participant) == Some(0)
+    }
+}
+
+//The screens shown over whatever else is going on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Dialog {
+    Settings,
+    Statistics,
+    //which page of them is showing
+    Rules(usize),
+    //opened from `Settings`, and goes back there when done
+    HouseRules,
+    //opened from `HouseRules`, and goes back there when done
+    CardPoints,
+}
+
+//Kept across games in their own file, see `save::save_statistics`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Statistics {
+    pub deals_played: u32,
+    pub deals_won: u32,
+    pub thirty_ones: u32,
+    pub matches_played: u32,
+    pub matches_won: u32,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics {
+            deals_played: 0,
+            deals_won: 0,
+            thirty_ones: 0,
+            matches_played: 0,
+            matches_won: 0,
+        }
+    }
+}
+
+//Things the player chose that should outlive a single game. These are kept in
+//their own file, see `save::save_settings`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub theme: Theme,
+    pub difficulty: Difficulty,
+    //`None` means a random number each deal
+    pub opponents: Option<usize>,
+    pub rules: Ruleset,
+    //the overlay showing which cards have turned up
+    pub show_tracker: bool,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            theme: Theme::Classic,
+            difficulty: Difficulty::Normal,
+            opponents: None,
+            rules: Ruleset::new(),
+            show_tracker: false,
+        }
+    }
+}
+
+pub const MAX_OPPONENTS: usize = 9;
+//A table bigger than this is something to choose, so a random number of
+//opponents stays within it.
+pub const MAX_RANDOM_OPPONENTS: usize = 4;
+
+//The house rules in effect. These only change between deals.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Ruleset {
+    //whether anyone can knock before the turn has come around once
+    pub knock_on_first_round: bool,
+    pub knocker_ties: KnockerTies,
+    //how many lives a knocker with the lowest hand loses
+    pub knocker_penalty: u32,
+    //whether a 31 in the hands as dealt wins before anyone moves
+    pub blitz_on_deal: bool,
+    //whether everyone else loses a life to a 31, rather than just the worst hand
+    pub blitz_everyone_loses: bool,
+    //whether a 31 stays hidden until the next knock, instead of ending the deal
+    //straight away
+    pub hidden_31: bool,
+    //how many cards everyone holds
+    pub hand_size: usize,
+    //how many cards of one suit count towards a score, at most `hand_size`
+    pub cards_scored: usize,
+    //how many standard decks are shuffled together into the deck, see `shoe`
+    pub decks: usize,
+    pub card_points: CardPoints,
+    //whether each deck comes with two jokers, see `Card::jokers`
+    pub jokers: bool,
+}
+
+impl Ruleset {
+    pub fn new() -> Self {
+        Ruleset {
+            knock_on_first_round: true,
+            knocker_ties: KnockerTies::Shared,
+            knocker_penalty: 2,
+            blitz_on_deal: false,
+            blitz_everyone_loses: false,
+            hidden_31: false,
+            hand_size: 3,
+            cards_scored: 3,
+            decks: 1,
+            card_points: CardPoints::ace_high(),
+            jokers: false,
+        }
+    }
+
+    //The most the `cards_scored` best values can add up to. With the standard
+    //card points that's an ace and the rest ten point cards, so 31 when three
+    //cards are scored and 41 when four are.
+    pub fn best_score(&self) -> u8 {
+        let mut points: Vec<u8> = Value::all_values()
+            .into_iter()
+            .map(|value| self.card_points.of(value))
+            .collect();
+
+        points.sort_by(|a, b| b.cmp(a));
+
+        points.iter().take(self.cards_scored).sum()
+    }
+
+    //Whether three of a kind scores 30½. That's meant to sit just under the
+    //best hand, and `Score` puts it just under 31, so with card points or a
+    //hand size that make the best hand anything else, it doesn't apply.
+    pub fn has_thirty_and_a_half(&self) -> bool {
+        self.cards_scored == 3 && self.best_score() == 31
+    }
+}
+
+//How many points a card of each value scores, in `Value::all_values` order.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CardPoints(pub [u8; 13]);
+
+pub const MAX_CARD_POINTS: u8 = 11;
+
+impl CardPoints {
+    //aces 11, face cards 10 and everything else its number
+    pub fn ace_high() -> Self {
+        CardPoints([11, 2, 3, 4, 5, 6, 7, 8, 9, 10, 10, 10, 10])
+    }
+
+    //the same, except aces are only worth 1
+    pub fn ace_low() -> Self {
+        CardPoints([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 10, 10, 10])
+    }
+
+    //Jokers are worth nothing until they stand in for another card, see
+    //`jokers_played`.
+    pub fn of(&self, value: Value) -> u8 {
+        value.index().map(|i| self.0[i]).unwrap_or(0)
+    }
+
+    pub fn set(&mut self, value: Value, points: u8) {
+        if let Some(i) = value.index() {
+            self.0[i] = points;
+        }
+    }
+}
+
+impl fmt::Display for CardPoints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == CardPoints::ace_high() {
+            write!(f, "Ace high")
+        } else if *self == CardPoints::ace_low() {
+            write!(f, "Ace low")
+        } else {
+            write!(f, "Custom")
+        }
+    }
+}
+
+pub const MAX_KNOCKER_PENALTY: u32 = 3;
+
+pub const MIN_HAND_SIZE: usize = 3;
+pub const MAX_HAND_SIZE: usize = 4;
+
+pub const MAX_DECKS: usize = 3;
+
+//What happens when the knocker's hand ties with someone else's, for the best
+//hand or the worst.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KnockerTies {
+    //everyone tied shares the win, or the loss
+    Shared,
+    //the knocker loses the tie either way
+    AgainstKnocker,
+    //the knocker wins the tie either way
+    ForKnocker,
+}
+
+impl fmt::Display for KnockerTies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match *self {
+            KnockerTies::Shared => "Shared",
+            KnockerTies::AgainstKnocker => "Knocker loses",
+            KnockerTies::ForKnocker => "Knocker wins",
+        })
+    }
+}
+
+impl AllValues for KnockerTies {
+    fn all_values() -> Vec<KnockerTies> {
+        vec![KnockerTies::Shared, KnockerTies::AgainstKnocker, KnockerTies::ForKnocker]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match *self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        })
+    }
+}
+
+impl AllValues for Difficulty {
+    fn all_values() -> Vec<Difficulty> {
+        vec![Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Theme {
+    Classic,
+    HighContrast,
+    //a four colour deck using colours that stay distinct with the common
+    //kinds of colour blindness
+    ColorblindSafe,
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match *self {
+            Theme::Classic => "Classic",
+            Theme::HighContrast => "High contrast",
+            Theme::ColorblindSafe => "Colorblind safe",
+        })
+    }
+}
+
+impl AllValues for Theme {
+    fn all_values() -> Vec<Theme> {
+        vec![Theme::Classic, Theme::HighContrast, Theme::ColorblindSafe]
+    }
+}
+
+//Somewhere a card can be on the table. These are turned into actual positions
+//each frame, so animations keep working if the window is resized.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Spot {
+    Deck,
+    Pile,
+    //where the card you picked up waits while you choose a discard
+    Selected,
+    PlayerHand(usize),
+    Seat(usize),
+}
+
+#[derive(Clone)]
+pub struct Tween {
+    //`None` means the card is face down
+    pub card: Option<Card>,
+    pub from: Spot,
+    pub to: Spot,
+    pub start_frame: u32,
+    pub duration: u32,
+}
+
+impl Tween {
+    pub fn end_frame(&self) -> u32 {
+        self.start_frame + self.duration
+    }
+
+    pub fn has_started(&self, frame: u32) -> bool {
+        frame >= self.start_frame
+    }
+
+    pub fn is_done(&self, frame: u32) -> bool {
+        frame >= self.end_frame()
+    }
+
+    //from 0.0 at `start_frame` to 1.0 at `end_frame()`
+    pub fn progress(&self, frame: u32) -> f32 {
+        if self.duration == 0 || self.is_done(frame) {
+            1.0
+        } else if !self.has_started(frame) {
+            0.0
+        } else {
+            (frame - self.start_frame) as f32 / self.duration as f32
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum Turn {
     PlayerTurn(Option<Participant>), //possible knocker
     PlayerSelected(Card, Option<Participant>), //possible knocker
-    CpuTurn(Option<Participant>), //possible knocker
+    CpuTurn(Option<KnockerOrWinner>, usize), //cpu taking its turn
     CpuSummary(Option<KnockerOrWinner>),
-    Resolution(Option<Participant>), //possible Winner
+    Resolution(Option<KnockerOrWinner>), //how the deal ended
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -69,6 +413,20 @@ pub struct UIContext {
     pub hot: UiId,
     pub active: UiId,
     pub next_hot: UiId,
+    //incremented once per `frame_init`
+    pub frame: u32,
+    //the frame `hot` last changed on
+    pub hot_since: u32,
+    //handed out by `new_id` and reset every frame, so widgets drawn in the same
+    //order every frame keep the same ids
+    next_id: UiId,
+    //whether a modal dialog was open last frame, in which case only the
+    //widgets inside it respond
+    modal: bool,
+    next_modal: bool,
+    in_modal: bool,
+    //which item of the current menu the keyboard is on
+    pub menu_focus: usize,
 }
 
 impl UIContext {
@@ -77,9 +435,33 @@ impl UIContext {
             hot: 0,
             active: 0,
             next_hot: 0,
+            frame: 0,
+            hot_since: 0,
+            next_id: 0,
+            modal: false,
+            next_modal: false,
+            in_modal: false,
+            menu_focus: 0,
         }
     }
 
+    pub fn new_id(&mut self) -> UiId {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn begin_modal(&mut self) {
+        self.next_modal = true;
+        self.in_modal = true;
+    }
+    pub fn end_modal(&mut self) {
+        self.in_modal = false;
+    }
+    //true for widgets underneath an open modal dialog
+    pub fn blocked(&self) -> bool {
+        self.modal && !self.in_modal
+    }
+
     pub fn set_not_active(&mut self) {
         self.active = 0;
     }
@@ -93,10 +475,19 @@ impl UIContext {
         self.hot = 0;
     }
     pub fn frame_init(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+
         if self.active == 0 {
+            if self.hot != self.next_hot {
+                self.hot_since = self.frame;
+            }
             self.hot = self.next_hot;
         }
         self.next_hot = 0;
+
+        self.next_id = 0;
+        self.modal = self.next_modal;
+        self.next_modal = false;
     }
 }
 
@@ -104,99 +495,304 @@ pub trait AllValues {
     fn all_values() -> Vec<Self> where Self: std::marker::Sized;
 }
 
+//`Ruleset::hand_size` cards, in the order they were dealt.
+#[derive(Clone)]
 pub enum HandEnum {
-    Hand(Card, Card, Card),
+    Hand(Vec<Card>),
 }
 use HandEnum::*;
 
 impl fmt::Display for HandEnum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = self.cards().iter().map(|card| format!("a {}", card)).collect();
 
-        write!(f, "{}", match *self {
-            Hand(ref c1, ref c2, ref c3) => format!("a {}, a {} and a {}", c1, c2, c3),
-        })
+        match names.split_last() {
+            Some((last, rest)) if !rest.is_empty() => {
+                write!(f, "{} and {}", rest.join(", "), last)
+            }
+            _ => write!(f, "{}", names.join("")),
+        }
     }
 }
 
 impl HandEnum {
-    pub fn swap(&mut self, index: HandCard, new_card: Card) -> Card {
-        match self {
-            &mut Hand(ref mut c1, ref mut c2, ref mut c3) => {
-                match index {
-                    FirstCard => {
-                        let result = c1.clone();
-                        *c1 = new_card;
-                        result
-                    }
-                    SecondCard => {
-                        let result = c2.clone();
-                        *c2 = new_card;
-                        result
-                    }
-                    ThirdCard => {
-                        let result = c3.clone();
-                        *c3 = new_card;
-                        result
-                    }
-                }
-            }
+    pub fn cards(&self) -> &[Card] {
+        match *self {
+            Hand(ref cards) => cards,
         }
     }
 
-    pub fn score(&self) -> Score {
-        match self {
-            &Hand(ref c1, ref c2, ref c3) => score_cards(c1, c2, c3),
+    //Puts `new_card` where the card at `index` was, and returns that card.
+    pub fn swap(&mut self, index: usize, new_card: Card) -> Card {
+        match *self {
+            Hand(ref mut cards) => std::mem::replace(&mut cards[index], new_card),
         }
     }
-    pub fn is_31(&self) -> bool {
-        match self.score() {
-            Simple(x) => x >= 31,
+
+    //What this hand would be after `swap`ping in `new_card` at `index`.
+    pub fn with_swapped(&self, index: usize, new_card: &Card) -> HandEnum {
+        let mut hand = self.clone();
+        hand.swap(index, new_card.clone());
+
+        hand
+    }
+
+    pub fn score(&self, rules: &Ruleset) -> Score {
+        self.breakdown(rules).score
+    }
+    pub fn breakdown(&self, rules: &Ruleset) -> ScoreBreakdown {
+        score_breakdown(self.cards(), rules)
+    }
+    //whether this is the best hand there is, see `Ruleset::best_score`.
+    pub fn is_31(&self, rules: &Ruleset) -> bool {
+        match self.score(rules) {
+            Simple(x) => x >= rules.best_score(),
             _ => false,
         }
     }
 }
 
-pub fn score_cards(c1: &Card, c2: &Card, c3: &Card) -> Score {
-    if c1.value == c2.value && c2.value == c3.value {
-        ThirtyAndAHalf
-    } else {
-        let mut clubs = Vec::new();
-        let mut diamonds = Vec::new();
-        let mut hearts = Vec::new();
-        let mut spades = Vec::new();
-
-        match c1.suit {
-            Clubs => clubs.push(c1.value),
-            Diamonds => diamonds.push(c1.value),
-            Hearts => hearts.push(c1.value),
-            Spades => spades.push(c1.value),
+pub fn score_cards(cards: &[Card], rules: &Ruleset) -> Score {
+    score_breakdown(cards, rules).score
+}
+
+//How a hand came to score what it did, see `score_breakdown`.
+#[derive(Clone, PartialEq)]
+pub struct ScoreBreakdown {
+    pub score: Score,
+    //the suit the score came from, or `None` for three of a kind
+    pub suit: Option<Suit>,
+    //the cards that count, most points first, with any jokers already swapped
+    //for what they stand in for
+    pub cards: Vec<Card>,
+    //what `cards` add up to, which with more than one deck can be more than
+    //the best hand, which is all it counts as
+    pub total: u8,
+    pub three_of_a_kind: bool,
+    //what each joker in the hand stood in for
+    pub jokers: Vec<Card>,
+}
+
+impl ScoreBreakdown {
+    //Something like "♣: A + K = 21".
+    pub fn explanation(&self) -> String {
+        let values: Vec<String> = self.cards.iter().map(|card| card.value.to_string()).collect();
+        let points = match self.score {
+            Simple(x) => x.to_string(),
+            ThirtyAndAHalf => "30½".to_string(),
+        };
+
+        let mut text = match self.suit {
+            Some(suit) if self.score != Simple(self.total) => {
+                format!("{}: {} = {}, counted as {}",
+                        suit,
+                        values.join(" + "),
+                        self.total,
+                        points)
+            }
+            Some(suit) => format!("{}: {} = {}", suit, values.join(" + "), points),
+            None => format!("{}, three of a kind = {}", values.join(" + "), points),
+        };
+
+        if !self.jokers.is_empty() {
+            let stand_ins: Vec<String> = self.jokers
+                .iter()
+                .map(|card| format!("{}{}", card.value, card.suit))
+                .collect();
+
+            text += &match stand_ins.split_last() {
+                         Some((last, rest)) if !rest.is_empty() => {
+                             format!(", with the jokers as {} and {}", rest.join(", "), last)
+                         }
+                         _ => format!(", with the joker as {}", stand_ins.join("")),
+                     };
         }
-        match c2.suit {
-            Clubs => clubs.push(c2.value),
-            Diamonds => diamonds.push(c2.value),
-            Hearts => hearts.push(c2.value),
-            Spades => spades.push(c2.value),
+
+        text
+    }
+}
+
+//The best suit total, see `suit_totals`. Under `Ruleset::has_thirty_and_a_half`,
+//three cards of the same value anywhere in the hand score 30½ instead. Any jokers
+//count as whatever is best, see `jokers_played`.
+pub fn score_breakdown(cards: &[Card], rules: &Ruleset) -> ScoreBreakdown {
+    let played = jokers_played(cards, rules);
+    let jokers = cards
+        .iter()
+        .zip(played.iter())
+        .filter(|&(card, _)| card.is_joker())
+        .map(|(_, stand_in)| stand_in.clone())
+        .collect();
+
+    ScoreBreakdown { jokers, ..breakdown_without_jokers(&played, rules) }
+}
+
+//`cards`, with each joker swapped for the card that makes the best hand.
+//
+//Rather than try every card in each joker's place, this only tries the ways a
+//joker can help: in each suit, the jokers standing in for the best cards of
+//that suit, and for three of a kind, the jokers making up the rest of it.
+pub fn jokers_played(cards: &[Card], rules: &Ruleset) -> Vec<Card> {
+    let joker_count = cards.iter().filter(|card| card.is_joker()).count();
+
+    if joker_count == 0 {
+        return cards.to_vec();
+    }
+
+    let points = |value: &Value| rules.card_points.of(*value);
+    let mut by_points = Value::all_values();
+    by_points.sort_by(|a, b| (points(b), b).cmp(&(points(a), a)));
+
+    let mut options = Vec::new();
+
+    for &suit in Suit::all_values().iter() {
+        let wanted: Vec<Card> = by_points.iter().map(|&value| Card { suit, value }).collect();
+
+        options.push(fill_in_jokers(cards, &wanted, rules));
+    }
+
+    if rules.has_thirty_and_a_half() {
+        for &value in by_points.iter() {
+            let count = cards.iter().filter(|card| card.value == value).count();
+
+            if count > 0 && count + joker_count >= 3 {
+                let wanted: Vec<Card> = Suit::all_values()
+                    .into_iter()
+                    .map(|suit| Card { suit, value })
+                    .collect();
+
+                options.push(fill_in_jokers(cards, &wanted, rules));
+            }
+        }
+    }
+
+    let mut best: Option<(Score, Vec<Card>)> = None;
+
+    for option in options {
+        let score = breakdown_without_jokers(&option, rules).score;
+
+        let is_better = match best {
+            Some((ref best_score, _)) => score > *best_score,
+            None => true,
+        };
+        if is_better {
+            best = Some((score, option));
         }
-        match c3.suit {
-            Clubs => clubs.push(c3.value),
-            Diamonds => diamonds.push(c3.value),
-            Hearts => hearts.push(c3.value),
-            Spades => spades.push(c3.value),
+    }
+
+    best.map(|(_, option)| option).unwrap_or_else(|| cards.to_vec())
+}
+
+//`cards`, with each joker standing in for the first of `wanted` there's still
+//a copy of. A card can't be in a hand more times than there are decks, so with
+//one deck a joker never doubles up a card already in the hand.
+fn fill_in_jokers(cards: &[Card], wanted: &[Card], rules: &Ruleset) -> Vec<Card> {
+    let mut result = cards.to_vec();
+
+    for i in 0..result.len() {
+        if !result[i].is_joker() {
+            continue;
         }
 
-        let int_resuilt = [clubs.iter().fold(0, |acc, v| acc + v.score()),
-                           diamonds.iter().fold(0, |acc, v| acc + v.score()),
-                           hearts.iter().fold(0, |acc, v| acc + v.score()),
-                           spades.iter().fold(0, |acc, v| acc + v.score())]
-                .iter()
-                .fold(0, |acc, x| std::cmp::max(acc, *x));
+        let available = wanted
+            .iter()
+            .find(|card| result.iter().filter(|c| c == card).count() < rules.decks)
+            .cloned();
 
-        Simple(int_resuilt)
+        if let Some(card) = available {
+            result[i] = card;
+        }
     }
+
+    result
+}
+
+fn breakdown_without_jokers(cards: &[Card], rules: &Ruleset) -> ScoreBreakdown {
+    let points = |card: &Card| rules.card_points.of(card.value);
+
+    let three_of_a_kind = if rules.has_thirty_and_a_half() {
+        cards
+            .iter()
+            .find(|card| cards.iter().filter(|c| c.value == card.value).count() >= 3)
+    } else {
+        None
+    };
+
+    if let Some(card) = three_of_a_kind {
+        let kind: Vec<Card> = cards
+            .iter()
+            .filter(|c| c.value == card.value)
+            .take(3)
+            .cloned()
+            .collect();
+
+        return ScoreBreakdown {
+                   score: ThirtyAndAHalf,
+                   suit: None,
+                   total: kind.iter().map(&points).sum(),
+                   cards: kind,
+                   three_of_a_kind: true,
+                   jokers: Vec::new(),
+               };
+    }
+
+    let mut best: Option<(Suit, Vec<Card>, u8)> = None;
+
+    for &suit in Suit::all_values().iter() {
+        let mut in_suit: Vec<Card> = cards.iter().filter(|c| c.suit == suit).cloned().collect();
+
+        in_suit.sort_by(|a, b| points(b).cmp(&points(a)));
+        in_suit.truncate(rules.cards_scored);
+
+        let total = in_suit.iter().map(&points).sum();
+
+        let is_better = match best {
+            Some((_, _, best_total)) => total > best_total,
+            None => true,
+        };
+        if is_better {
+            best = Some((suit, in_suit, total));
+        }
+    }
+
+    let (suit, cards, total) = best.unwrap_or((Clubs, Vec::new(), 0));
+
+    ScoreBreakdown {
+        //with more than one deck, say two aces of the same suit can add up to
+        //more than the best hand, but nothing beats it.
+        score: Simple(std::cmp::min(total, rules.best_score())),
+        suit: Some(suit),
+        cards,
+        total,
+        three_of_a_kind: false,
+        jokers: Vec::new(),
+    }
+}
+
+//What the best `Ruleset::cards_scored` cards of each suit add up to, in
+//`Suit::all_values` order.
+pub fn suit_totals(cards: &[Card], rules: &Ruleset) -> [(Suit, u8); 4] {
+    let mut totals = [(Clubs, 0), (Diamonds, 0), (Hearts, 0), (Spades, 0)];
+
+    for &mut (suit, ref mut total) in totals.iter_mut() {
+        let mut points: Vec<u8> = cards
+            .iter()
+            .filter(|card| card.suit == suit)
+            .map(|card| rules.card_points.of(card.value))
+            .collect();
+
+        points.sort_by(|a, b| b.cmp(a));
+
+        *total = points.iter().take(rules.cards_scored).sum();
+    }
+
+    totals
 }
 
 #[derive(PartialEq, Eq, Clone, Hash, Debug)]
 pub enum Score {
+    //only under `Ruleset::has_thirty_and_a_half`, which is why this can
+    //assume the best hand is 31.
     ThirtyAndAHalf,
     Simple(u8),
 }
@@ -228,14 +824,6 @@ impl PartialOrd for Score {
     }
 }
 
-#[derive(Copy, Clone)]
-pub enum HandCard {
-    FirstCard,
-    SecondCard,
-    ThirdCard,
-}
-use HandCard::*;
-
 #[derive(Eq, Clone)]
 pub struct Card {
     pub suit: Suit,
@@ -259,6 +847,42 @@ impl AllValues for Card {
     }
 }
 
+//`decks` standard decks together, in order, with their jokers if `jokers`.
+//Cards from different decks are indistinguishable, so a card can turn up as
+//many times as there are decks.
+pub fn shoe(decks: usize, jokers: bool) -> Vec<Card> {
+    let mut shoe = Vec::new();
+
+    for _ in 0..decks {
+        shoe.extend(Card::all_values());
+
+        if jokers {
+            shoe.extend(Card::jokers());
+        }
+    }
+
+    shoe
+}
+
+impl Card {
+    //A red one and a black one. Their suits are only there to say which is
+    //which, since they count as whatever card is best, see `jokers_played`.
+    pub fn jokers() -> Vec<Card> {
+        vec![Card {
+                 suit: Hearts,
+                 value: Joker,
+             },
+             Card {
+                 suit: Spades,
+                 value: Joker,
+             }]
+    }
+
+    pub fn is_joker(&self) -> bool {
+        self.value == Joker
+    }
+}
+
 impl Ord for Card {
     fn cmp(&self, other: &Card) -> Ordering {
         match self.suit.cmp(&other.suit) {
@@ -282,7 +906,11 @@ impl PartialEq for Card {
 
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} of {}", self.value, self.suit)
+        if self.is_joker() {
+            write!(f, "joker")
+        } else {
+            write!(f, "{} of {}", self.value, self.suit)
+        }
     }
 }
 
@@ -350,6 +978,8 @@ pub enum Value {
     Jack,
     Queen,
     King,
+    //wild, and not one of `Value::all_values`, see `Card::jokers`
+    Joker,
 }
 use Value::*;
 
@@ -369,6 +999,7 @@ impl fmt::Display for Value {
             Jack => "J".to_string(),
             Queen => "Q".to_string(),
             King => "K".to_string(),
+            Joker => "JK".to_string(),
         })
     }
 }
@@ -385,6 +1016,8 @@ impl Ord for Value {
     }
 }
 
+//The value's rank, for putting cards in order and saving them. What a card
+//scores is up to `Ruleset::card_points`.
 impl From<Value> for u8 {
     fn from(value: Value) -> Self {
         match value {
@@ -401,6 +1034,7 @@ impl From<Value> for u8 {
             Jack => 11,
             Queen => 12,
             King => 13,
+            Joker => 15,
         }
     }
 }
@@ -412,21 +1046,23 @@ impl PartialOrd for Value {
 }
 
 impl Value {
-    fn score(&self) -> u8 {
+    //where this is in `Value::all_values`, which jokers aren't
+    pub fn index(&self) -> Option<usize> {
         match *self {
-            Ace => 11, //Ace high
-            Two => 2,
-            Three => 3,
-            Four => 4,
-            Five => 5,
-            Six => 6,
-            Seven => 7,
-            Eight => 8,
-            Nine => 9,
-            Ten => 10,
-            Jack => 10,
-            Queen => 10,
-            King => 10,
+            Ace => Some(0),
+            Two => Some(1),
+            Three => Some(2),
+            Four => Some(3),
+            Five => Some(4),
+            Six => Some(5),
+            Seven => Some(6),
+            Eight => Some(7),
+            Nine => Some(8),
+            Ten => Some(9),
+            Jack => Some(10),
+            Queen => Some(11),
+            King => Some(12),
+            Joker => None,
         }
     }
 }
@@ -437,392 +1073,115 @@ pub enum KnockerOrWinner {
     Winner(Participant),
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn hand(cards: &[(Value, Suit)]) -> Vec<Card> {
+        cards.iter().map(|&(value, suit)| Card { suit, value }).collect()
+    }
 
+    //`Card` isn't `Debug`, so compare something that prints nicely instead.
+    fn names(cards: &[Card]) -> Vec<String> {
+        cards.iter().map(|card| format!("{}{}", card.value, card.suit)).collect()
+    }
 
+    fn jokers_rules() -> Ruleset {
+        Ruleset { jokers: true, ..Ruleset::new() }
+    }
 
+    #[test]
+    fn one_joker_completes_the_best_suit() {
+        let cards = hand(&[(Ace, Spades), (King, Spades), (Joker, Hearts)]);
+        let rules = jokers_rules();
 
+        //with one deck, there's only one A♠ and it's already in the hand.
+        assert_eq!(names(&jokers_played(&cards, &rules)), vec!["A♠", "K♠", "Q♠"]);
+        assert_eq!(score_cards(&cards, &rules), Simple(31));
+    }
 
-//NOTE(Ryan1729): if I import BearLibTerminal.rs into `state_manipulation` or a crate
-//`state_manipulation` depends on, like this one for example, then the
-//ffi to the C version of BearLibTerminal causes an error. I just want
-//the geometry datatypes and the Event and Keycode definitions so I have
-//copied them from BearLibTerminal.rs below
+    #[test]
+    fn two_jokers_stand_in_for_different_cards() {
+        let cards = hand(&[(Ace, Clubs), (Joker, Hearts), (Joker, Spades)]);
+        let rules = jokers_rules();
 
-//BearLibTerminal.rs is released under the MIT license by nabijaczleweli.
-//see https://github.com/nabijaczleweli/BearLibTerminal.rs/blob/master/LICENSE
-//for full details.
+        //three aces would only be 30½.
+        assert_eq!(names(&jokers_played(&cards, &rules)), vec!["A♣", "K♣", "Q♣"]);
+        assert_eq!(score_cards(&cards, &rules), Simple(31));
+    }
 
-impl Point {
-    /// Creates a new point on the specified non-negative coordinates
-    pub fn new_safe(mut x: i32, mut y: i32) -> Point {
-        x = if x >= 0 { x } else { 0 };
-        y = if y >= 0 { y } else { 0 };
+    #[test]
+    fn a_joker_can_make_three_of_a_kind() {
+        let cards = hand(&[(Four, Diamonds), (Four, Hearts), (Joker, Spades)]);
+        let rules = jokers_rules();
 
-        Point { x: x, y: y }
+        assert_eq!(names(&jokers_played(&cards, &rules)), vec!["4♦", "4♥", "4♣"]);
+        assert_eq!(score_cards(&cards, &rules), ThirtyAndAHalf);
     }
 
-    pub fn add(&self, x: i32, y: i32) -> Point {
-        Point::new_safe(self.x + x, self.y + y)
+    #[test]
+    fn jokers_make_no_thirty_and_a_half_with_aces_low() {
+        let cards = hand(&[(Four, Diamonds), (Four, Hearts), (Joker, Spades)]);
+        let rules = Ruleset { card_points: CardPoints::ace_low(), ..jokers_rules() };
+
+        assert_eq!(names(&jokers_played(&cards, &rules)), vec!["4♦", "4♥", "K♦"]);
+        assert_eq!(score_cards(&cards, &rules), Simple(14));
     }
-}
 
-/// Represents a single on-screen point/coordinate pair.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Point {
-    pub x: i32,
-    pub y: i32,
-}
+    #[test]
+    fn a_joker_can_double_up_a_card_with_more_than_one_deck() {
+        let cards = hand(&[(Ace, Spades), (King, Spades), (Joker, Hearts)]);
+        let rules = Ruleset { decks: 2, ..jokers_rules() };
 
-impl Point {
-    /// Creates a new point on the specified non-negative coordinates
-    pub fn new(x: i32, y: i32) -> Point {
-        assert!(x >= 0);
-        assert!(y >= 0);
+        assert_eq!(names(&jokers_played(&cards, &rules)), vec!["A♠", "K♠", "A♠"]);
 
-        Point { x: x, y: y }
+        let breakdown = score_breakdown(&cards, &rules);
+        assert_eq!(breakdown.total, 32);
+        assert_eq!(breakdown.score, Simple(31));
     }
-}
+    #[test]
+    fn explanation_adds_up_the_best_suit() {
+        let cards = hand(&[(Ace, Clubs), (King, Clubs), (Two, Hearts)]);
 
+        assert_eq!(score_breakdown(&cards, &Ruleset::new()).explanation(), "♣: A + K = 21");
+    }
 
-/// A 2D size representation.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Size {
-    pub width: i32,
-    pub height: i32,
-}
+    #[test]
+    fn explanation_says_when_a_total_is_capped() {
+        let cards = hand(&[(Ace, Spades), (Ace, Spades), (King, Spades)]);
+        let rules = Ruleset { decks: 2, ..Ruleset::new() };
 
-impl Size {
-    /// Creates a new non-negative size.
-    pub fn new(width: i32, height: i32) -> Size {
-        assert!(width >= 0);
-        assert!(height >= 0);
+        assert_eq!(score_breakdown(&cards, &rules).explanation(),
+                   "♠: A + A + K = 32, counted as 31");
+    }
 
-        Size {
-            width: width,
-            height: height,
-        }
+    #[test]
+    fn explanation_of_three_of_a_kind() {
+        let cards = hand(&[(Seven, Clubs), (Seven, Diamonds), (Seven, Hearts)]);
+
+        assert_eq!(score_breakdown(&cards, &Ruleset::new()).explanation(),
+                   "7 + 7 + 7, three of a kind = 30½");
     }
-}
 
-impl fmt::Display for Size {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}x{}", self.width, self.height)
-    }
-}
-
-/// A rectangle, described by its four corners and a size.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Rect {
-    /// The top-left corner.
-    pub top_left: Point,
-    /// The top-right corner.
-    pub top_right: Point,
-    /// The bottom-right corner.
-    pub bottom_right: Point,
-    /// The bottom-left corner.
-    pub bottom_left: Point,
-    /// The `Rect`angle's size.
-    pub size: Size,
-}
-
-impl Rect {
-    /// Construct a `Rect` from its top-left corner and its size.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use bear_lib_terminal::geometry::{Rect, Point, Size};
-    /// let rect = Rect::from_size(Point::new(10, 20), Size::new(30, 40));
-    /// assert_eq!(rect.top_left, Point::new(10, 20));
-    /// assert_eq!(rect.top_right, Point::new(40, 20));
-    /// assert_eq!(rect.bottom_left, Point::new(10, 60));
-    /// assert_eq!(rect.bottom_right, Point::new(40, 60));
-    /// assert_eq!(rect.size, Size::new(30, 40));
-    /// ```
-    pub fn from_size(origin: Point, size: Size) -> Rect {
-        let top_right = Point::new(origin.x + size.width, origin.y);
-        let bottom_left = Point::new(origin.x, origin.y + size.height);
-        let bottom_right = Point::new(top_right.x, bottom_left.y);
-
-        Rect {
-            top_left: origin,
-            top_right: top_right,
-            bottom_left: bottom_left,
-            bottom_right: bottom_right,
-            size: size,
-        }
+    #[test]
+    fn explanation_says_what_a_joker_stood_in_for() {
+        let cards = hand(&[(Ace, Spades), (King, Spades), (Joker, Hearts)]);
+
+        assert_eq!(score_breakdown(&cards, &jokers_rules()).explanation(),
+                   "♠: A + K + Q = 31, with the joker as Q♠");
     }
 
-    /// Construct a `Rect` from its top-left and bottom-right corners.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use bear_lib_terminal::geometry::{Rect, Point, Size};
-    /// let rect = Rect::from_points(Point::new(10, 20), Point::new(30, 40));
-    /// assert_eq!(rect.top_left, Point::new(10, 20));
-    /// assert_eq!(rect.top_right, Point::new(30, 20));
-    /// assert_eq!(rect.bottom_left, Point::new(10, 40));
-    /// assert_eq!(rect.bottom_right, Point::new(30, 40));
-    /// assert_eq!(rect.size, Size::new(20, 20));
-    /// ```
-    pub fn from_points(top_left: Point, bottom_right: Point) -> Rect {
-        assert!(bottom_right.x >= top_left.x);
-        assert!(bottom_right.y >= top_left.y);
-
-        let size = Size::new(bottom_right.x - top_left.x, bottom_right.y - top_left.y);
-        Rect::from_size(top_left, size)
-    }
-
-    /// Construct a `Rect` from its top-left corner and its size, values unwrapped.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use bear_lib_terminal::geometry::{Rect, Point, Size};
-    /// assert_eq!(Rect::from_values(10, 20, 30, 40),
-    ///     Rect::from_size(Point::new(10, 20), Size::new(30, 40)));
-    /// ```
-    pub fn from_values(x: i32, y: i32, width: i32, height: i32) -> Rect {
-        let origin = Point::new(x, y);
-        let size = Size::new(width, height);
-        Rect::from_size(origin, size)
-    }
-
-
-    /// Construct a `Rect` from its top-left and bottom-right corners, values unwrapped.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use bear_lib_terminal::geometry::{Rect, Point, Size};
-    /// assert_eq!(Rect::from_point_values(10, 20, 30, 40),
-    ///     Rect::from_points(Point::new(10, 20), Point::new(30, 40)));
-    /// ```
-    pub fn from_point_values(top_left_x: i32,
-                             top_left_y: i32,
-                             bottom_right_x: i32,
-                             bottom_right_y: i32)
-                             -> Rect {
-        let top_left = Point::new(top_left_x, top_left_y);
-        let bottom_right = Point::new(bottom_right_x, bottom_right_y);
-        Rect::from_points(top_left, bottom_right)
-    }
-}
-
-//input module
-
-/// All pressable keys.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum KeyCode {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-    H,
-    I,
-    J,
-    K,
-    L,
-    M,
-    N,
-    O,
-    P,
-    Q,
-    R,
-    S,
-    T,
-    U,
-    V,
-    W,
-    X,
-    Y,
-    Z,
-    /// Top-row `1/!` key.
-    Row1,
-    /// Top-row `2/@` key.
-    Row2,
-    /// Top-row `3/#` key.
-    Row3,
-    /// Top-row `4/$` key.
-    Row4,
-    /// Top-row `5/%` key.
-    Row5,
-    /// Top-row `6/^` key.
-    Row6,
-    /// Top-row `7/&` key.
-    Row7,
-    /// Top-row `8/*` key.
-    Row8,
-    /// Top-row `9/(` key.
-    Row9,
-    /// Top-row `0/)` key.
-    Row0,
-    /// Top-row &#96;/~ key.
-    Grave,
-    /// Top-row `-/_` key.
-    Minus,
-    /// Top-row `=/+` key.
-    Equals,
-    /// Second-row `[/{` key.
-    LeftBracket,
-    /// Second-row `]/}` key.
-    RightBracket,
-    /// Second-row `\/|` key.
-    Backslash,
-    /// Third-row `;/:` key.
-    Semicolon,
-    /// Third-row `'/"` key.
-    Apostrophe,
-    /// Fourth-row `,/<` key.
-    Comma,
-    /// Fourth-row `./>` key.
-    Period,
-    /// Fourth-row `//?` key.
-    Slash,
-    F1,
-    F2,
-    F3,
-    F4,
-    F5,
-    F6,
-    F7,
-    F8,
-    F9,
-    F10,
-    F11,
-    F12,
-    Enter,
-    Escape,
-    Backspace,
-    Tab,
-    Space,
-    Pause,
-    Insert,
-    Home,
-    PageUp,
-    Delete,
-    End,
-    PageDown,
-    /// Right arrow key.
-    Right,
-    /// Left arrow key.
-    Left,
-    /// Down arrow key.
-    Down,
-    /// Up arrow key.
-    Up,
-    /// Numpad `/` key.
-    NumDivide,
-    /// Numpad `*` key.
-    NumMultiply,
-    /// Numpad `-` key.
-    NumMinus,
-    /// Numpad `+` key.
-    NumPlus,
-    /// Numpad &#9166; key.
-    NumEnter,
-    /// Numpad `Del/.` key (output locale-dependent).
-    NumPeriod,
-    /// Numpad `1/End` key.
-    Num1,
-    /// Numpad 2/&#8595; key.
-    Num2,
-    /// Numpad `3/PageDown` key.
-    Num3,
-    /// Numpad 4/&#8592; key.
-    Num4,
-    /// Numpad `5` key.
-    Num5,
-    /// Numpad 6/&#8594; key.
-    Num6,
-    /// Numpad `7/Home` key.
-    Num7,
-    /// Numpad 8/&#8593; key.
-    Num8,
-    /// Numpad `9/PageUp` key.
-    Num9,
-    /// Numpad `0/Insert` key.
-    Num0,
-    /// Left mouse button.
-    MouseLeft,
-    /// Right mouse button.
-    MouseRight,
-    /// Middle mouse button a.k.a. pressed scroll wheel.
-    MouseMiddle,
-    MouseFourth,
-    MouseFifth,
-}
-
-/// A single input event.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Event {
-    /// Terminal window closed.
-    Close,
-    /// Terminal window resized. Needs to have `window.resizeable = true` to occur.
-    ///
-    /// Note, that the terminal window is cleared when resized.
-    Resize {
-        /// Width the terminal was resized to.
-        width: i32,
-        /// Heigth the terminal was resized to.
-        height: i32,
-    },
-    /// Mouse moved.
-    ///
-    /// If [`precise-mouse`](config/struct.Input.html#structfield.precise_mouse) is off,
-    /// generated each time mouse moves from cell to cell, otherwise,
-    /// when it moves from pixel to pixel.
-    MouseMove {
-        /// `0`-based cell index from the left to which the mouse cursor moved.
-        x: i32,
-        /// `0`-based cell index from the top to which the mouse cursor moved.
-        y: i32,
-    },
-    /// Mouse wheel moved.
-    MouseScroll {
-        /// Amount of steps the wheel rotated.
-        ///
-        /// Positive when scrolled "down"/"backwards".
-        ///
-        /// Negative when scrolled "up"/"forwards"/"away".
-        delta: i32,
-    },
-    /// A keyboard or mouse button pressed (might repeat, if set in OS).
-    KeyPressed {
-        /// The key pressed.
-        key: KeyCode,
-        /// Whether the Control key is pressed.
-        ctrl: bool,
-        /// Whether the Shift key is pressed.
-        shift: bool,
-    },
-    /// A keyboard or mouse button released.
-    KeyReleased {
-        /// The key released.
-        key: KeyCode,
-        /// Whether the Control key is pressed.
-        ctrl: bool,
-        /// Whether the Shift key is pressed.
-        shift: bool,
-    },
-    /// The Shift key pressed (might repeat, if set in OS).
-    ShiftPressed,
-    /// The Shift key released.
-    ShiftReleased,
-    /// The Shift key pressed (might repeat, if set in OS).
-    ControlPressed,
-    /// The Control key released.
-    ControlReleased,
-}
-
-pub struct Color {
-    pub red: u8,
-    pub green: u8,
-    pub blue: u8,
-    pub alpha: u8,
+    #[test]
+    fn explanation_lists_what_several_jokers_stood_in_for() {
+        let cards = hand(&[(Ace, Clubs), (Joker, Hearts), (Joker, Spades), (Joker, Hearts)]);
+        let rules = Ruleset {
+            decks: 2,
+            hand_size: 4,
+            cards_scored: 4,
+            ..jokers_rules()
+        };
+
+        assert_eq!(score_breakdown(&cards, &rules).explanation(),
+                   "♣: A + A + K + K = 42, counted as 41, with the jokers as A♣, K♣ and K♣");
+    }
 }
@@ -0,0 +1,164 @@
+use super::*;
+
+//Everything in the shoe `rules` call for that isn't in `hand` or `known`, the
+//cards whose whereabouts are already known. Each card in those only accounts
+//for one copy, so with more than one deck the other copies are still unseen.
+//From where the player sits, any of these is as likely as any other to be the
+//next card off the deck.
+pub fn unseen_cards(hand: &HandEnum, known: &[Card], rules: &Ruleset) -> Vec<Card> {
+    let mut unseen = shoe(rules.decks, rules.jokers);
+
+    for card in hand.cards().iter().chain(known.iter()) {
+        if let Some(index) = unseen.iter().position(|c| c == card) {
+            unseen.remove(index);
+        }
+    }
+
+    unseen
+}
+
+//The best hand that can be made by taking `card` and putting one card back.
+//Ties keep the hand as it was.
+pub fn best_with(hand: &HandEnum, card: &Card, rules: &Ruleset) -> HandEnum {
+    let mut best = hand.clone();
+    let mut best_score = hand.score(rules);
+
+    for i in 0..hand.cards().len() {
+        let option = hand.with_swapped(i, card);
+        let score = option.score(rules);
+
+        if score > best_score {
+            best = option;
+            best_score = score;
+        }
+    }
+
+    best
+}
+
+//For averaging. 30½ is worth, well, 30.5.
+pub fn score_value(score: &Score) -> f32 {
+    match *score {
+        Simple(x) => x as f32,
+        ThirtyAndAHalf => 30.5,
+    }
+}
+
+//Each different card in `cards`, and how many copies of it there are. With
+//more than one deck, drawing any copy leads to the same hand, so this saves
+//working each of them out separately.
+fn copies(cards: &[Card]) -> Vec<(Card, usize)> {
+    let mut result: Vec<(Card, usize)> = Vec::new();
+
+    for card in cards.iter() {
+        if let Some(entry) = result.iter_mut().find(|&&mut (ref c, _)| c == card) {
+            entry.1 += 1;
+            continue;
+        }
+
+        result.push((card.clone(), 1));
+    }
+
+    result
+}
+
+//The chance of having at least `target` within `draws` more draws off the
+//deck, keeping the best hand after each one. `unseen` is what those draws could
+//be, see `unseen_cards`. This looks at every order the draws could come in, so
+//keep `draws` small.
+pub fn chance_of_reaching(hand: &HandEnum,
+                          unseen: &[Card],
+                          target: &Score,
+                          draws: u32,
+                          rules: &Ruleset)
+                          -> f32 {
+    if hand.score(rules) >= *target {
+        return 1.0;
+    }
+
+    if draws == 0 || unseen.is_empty() {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+
+    for (card, count) in copies(unseen) {
+        let mut rest = unseen.to_vec();
+        if let Some(index) = rest.iter().position(|c| *c == card) {
+            rest.remove(index);
+        }
+
+        let chance = chance_of_reaching(&best_with(hand, &card, rules),
+                                        &rest,
+                                        target,
+                                        draws - 1,
+                                        rules);
+
+        total += chance * count as f32;
+    }
+
+    total / unseen.len() as f32
+}
+
+//The average score after drawing one card off the deck and keeping the best
+//hand.
+pub fn expected_score_from_deck(hand: &HandEnum, unseen: &[Card], rules: &Ruleset) -> f32 {
+    if unseen.is_empty() {
+        return score_value(&hand.score(rules));
+    }
+
+    let total: f32 = copies(unseen)
+        .iter()
+        .map(|&(ref card, count)| {
+                 score_value(&best_with(hand, card, rules).score(rules)) * count as f32
+             })
+        .sum();
+
+    total / unseen.len() as f32
+}
+
+//The score after taking `pile_top` and keeping the best hand. Unlike the deck
+//there's nothing to average, since the pile is face up.
+pub fn score_from_pile(hand: &HandEnum, pile_top: &Card, rules: &Ruleset) -> f32 {
+    score_value(&best_with(hand, pile_top, rules).score(rules))
+}
+
+//What the score panel shows during the player's turn. It takes long enough to
+//work out that it's kept on `State`, and only worked out again once the hand
+//or the pile has changed, see `is_for`.
+#[derive(Clone, PartialEq)]
+pub struct Summary {
+    hand: Vec<Card>,
+    pile: Vec<Card>,
+    //the score after taking the top of the pile, if there is one
+    pub from_pile: Option<f32>,
+    pub from_deck: f32,
+    //the chance of the best hand there is within `draws` more draws
+    pub chance_of_best: f32,
+    pub draws: u32,
+}
+
+impl Summary {
+    pub fn new(hand: &HandEnum, pile: &[Card], draws: u32, rules: &Ruleset) -> Self {
+        //the pile is all face up, so only the deck and the cpus' hands are a
+        //mystery.
+        let unseen = unseen_cards(hand, pile, rules);
+
+        Summary {
+            hand: hand.cards().to_vec(),
+            pile: pile.to_vec(),
+            from_pile: pile.last().map(|top| score_from_pile(hand, top, rules)),
+            from_deck: expected_score_from_deck(hand, &unseen, rules),
+            chance_of_best: chance_of_reaching(hand,
+                                               &unseen,
+                                               &Simple(rules.best_score()),
+                                               draws,
+                                               rules),
+            draws,
+        }
+    }
+
+    pub fn is_for(&self, hand: &HandEnum, pile: &[Card]) -> bool {
+        self.hand.as_slice() == hand.cards() && self.pile.as_slice() == pile
+    }
+}
@@ -0,0 +1,814 @@
+use std::fmt;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use super::*;
+
+//Bump this whenever the saved shape of `State` changes, and teach the
+//relevant `load` impl how to read the older versions via `reader.version`.
+pub const STATE_VERSION: u32 = 1;
+
+const MAGIC: &'static [u8] = b"T31S";
+
+const SEED_LEN: usize = 4;
+
+#[derive(Debug)]
+pub enum LoadError {
+    BadMagic,
+    UnknownVersion(u32),
+    UnexpectedEnd,
+    Invalid(&'static str),
+}
+use self::LoadError::*;
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BadMagic => write!(f, "not a saved state"),
+            UnknownVersion(v) => {
+                write!(f,
+                       "saved state version {} is newer than this build's version {}",
+                       v,
+                       STATE_VERSION)
+            }
+            UnexpectedEnd => write!(f, "saved state was truncated"),
+            Invalid(what) => write!(f, "saved state contained an invalid {}", what),
+        }
+    }
+}
+
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+
+        let mut writer = Writer { bytes };
+        writer.u32(STATE_VERSION);
+
+        writer
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+    pub fn u32(&mut self, v: u32) {
+        for i in 0..4 {
+            self.u8((v >> (i * 8)) as u8);
+        }
+    }
+    pub fn u64(&mut self, v: u64) {
+        for i in 0..8 {
+            self.u8((v >> (i * 8)) as u8);
+        }
+    }
+    pub fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize,
+    //the `STATE_VERSION` of the build that wrote `bytes`
+    pub version: u32,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, LoadError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(BadMagic);
+        }
+
+        let mut reader = Reader {
+            bytes,
+            index: MAGIC.len(),
+            version: 0,
+        };
+
+        reader.version = reader.u32()?;
+
+        if reader.version > STATE_VERSION || reader.version == 0 {
+            Err(UnknownVersion(reader.version))
+        } else {
+            Ok(reader)
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, LoadError> {
+        let result = self.bytes.get(self.index).cloned().ok_or(UnexpectedEnd);
+        self.index += 1;
+        result
+    }
+    pub fn bool(&mut self) -> Result<bool, LoadError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Invalid("bool")),
+        }
+    }
+    pub fn u32(&mut self) -> Result<u32, LoadError> {
+        let mut result = 0;
+        for i in 0..4 {
+            result |= (self.u8()? as u32) << (i * 8);
+        }
+        Ok(result)
+    }
+    pub fn u64(&mut self) -> Result<u64, LoadError> {
+        let mut result = 0;
+        for i in 0..8 {
+            result |= (self.u8()? as u64) << (i * 8);
+        }
+        Ok(result)
+    }
+    pub fn str(&mut self) -> Result<String, LoadError> {
+        let len = self.u32()? as usize;
+        let end = self.index + len;
+        let bytes = self.bytes.get(self.index..end).ok_or(UnexpectedEnd)?;
+        self.index = end;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| Invalid("string"))
+    }
+}
+
+pub trait Save {
+    fn save(&self, writer: &mut Writer);
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> where Self: std::marker::Sized;
+}
+
+impl<T: Save> Save for Vec<T> {
+    fn save(&self, writer: &mut Writer) {
+        writer.u32(self.len() as u32);
+        for element in self.iter() {
+            element.save(writer);
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let len = reader.u32()?;
+        let mut result = Vec::new();
+        for _ in 0..len {
+            result.push(T::load(reader)?);
+        }
+        Ok(result)
+    }
+}
+
+impl<T: Save> Save for Option<T> {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            Some(ref t) => {
+                writer.bool(true);
+                t.save(writer);
+            }
+            None => writer.bool(false),
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        if reader.bool()? {
+            T::load(reader).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Save for u32 {
+    fn save(&self, writer: &mut Writer) {
+        writer.u32(*self);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        reader.u32()
+    }
+}
+
+impl Save for String {
+    fn save(&self, writer: &mut Writer) {
+        writer.str(self);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        reader.str()
+    }
+}
+
+impl Save for Suit {
+    fn save(&self, writer: &mut Writer) {
+        writer.u8(u8::from(*self));
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let n = reader.u8()?;
+        Suit::all_values()
+            .into_iter()
+            .find(|&suit| u8::from(suit) == n)
+            .ok_or(Invalid("suit"))
+    }
+}
+
+impl Save for Value {
+    fn save(&self, writer: &mut Writer) {
+        writer.u8(u8::from(*self));
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let n = reader.u8()?;
+        Value::all_values()
+            .into_iter()
+            .chain(std::iter::once(Joker))
+            .find(|&value| u8::from(value) == n)
+            .ok_or(Invalid("value"))
+    }
+}
+
+impl Save for Card {
+    fn save(&self, writer: &mut Writer) {
+        self.suit.save(writer);
+        self.value.save(writer);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(Card {
+               suit: Suit::load(reader)?,
+               value: Value::load(reader)?,
+           })
+    }
+}
+
+impl Save for HandEnum {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            Hand(ref cards) => {
+                writer.u8(0);
+                cards.save(writer);
+            }
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        match reader.u8()? {
+            0 => Vec::load(reader).map(Hand),
+            _ => Err(Invalid("hand")),
+        }
+    }
+}
+
+impl Save for Participant {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            Player => writer.u8(0),
+            Cpu(i) => {
+                writer.u8(1);
+                writer.u32(i as u32);
+            }
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        match reader.u8()? {
+            0 => Ok(Player),
+            1 => Ok(Cpu(reader.u32()? as usize)),
+            _ => Err(Invalid("participant")),
+        }
+    }
+}
+
+impl Save for KnockerOrWinner {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            KnockerOrWinner::Knocker(ref p) => {
+                writer.u8(0);
+                p.save(writer);
+            }
+            KnockerOrWinner::Winner(ref p) => {
+                writer.u8(1);
+                p.save(writer);
+            }
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        match reader.u8()? {
+            0 => Participant::load(reader).map(KnockerOrWinner::Knocker),
+            1 => Participant::load(reader).map(KnockerOrWinner::Winner),
+            _ => Err(Invalid("knocker or winner")),
+        }
+    }
+}
+
+impl Save for Turn {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            Turn::PlayerTurn(ref p) => {
+                writer.u8(0);
+                p.save(writer);
+            }
+            Turn::PlayerSelected(ref card, ref p) => {
+                writer.u8(1);
+                card.save(writer);
+                p.save(writer);
+            }
+            Turn::CpuTurn(ref kw, i) => {
+                writer.u8(2);
+                kw.save(writer);
+                writer.u32(i as u32);
+            }
+            Turn::CpuSummary(ref kw) => {
+                writer.u8(3);
+                kw.save(writer);
+            }
+            Turn::Resolution(ref kw) => {
+                writer.u8(4);
+                kw.save(writer);
+            }
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        match reader.u8()? {
+            0 => Option::load(reader).map(Turn::PlayerTurn),
+            1 => {
+                let card = Card::load(reader)?;
+                Option::load(reader).map(|p| Turn::PlayerSelected(card, p))
+            }
+            2 => {
+                let kw = Option::load(reader)?;
+
+                Ok(Turn::CpuTurn(kw, reader.u32()? as usize))
+            }
+            3 => Option::load(reader).map(Turn::CpuSummary),
+            4 => Option::load(reader).map(Turn::Resolution),
+            _ => Err(Invalid("turn")),
+        }
+    }
+}
+
+//Settings like these are saved as their position in `all_values`, so they
+//don't depend on how the enum happens to be declared.
+fn save_index<T: AllValues + PartialEq>(value: &T, writer: &mut Writer) {
+    let index = T::all_values().iter().position(|v| v == value).unwrap_or(0);
+
+    writer.u8(index as u8);
+}
+
+fn load_index<T: AllValues>(reader: &mut Reader, what: &'static str) -> Result<T, LoadError> {
+    let index = reader.u8()? as usize;
+
+    T::all_values().into_iter().nth(index).ok_or(Invalid(what))
+}
+
+macro_rules! save_as_index {
+    ($t: ty, $what: expr) => {
+        impl Save for $t {
+            fn save(&self, writer: &mut Writer) {
+                save_index(self, writer);
+            }
+            fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+                load_index(reader, $what)
+            }
+        }
+    }
+}
+
+save_as_index!(Theme, "theme");
+save_as_index!(Difficulty, "difficulty");
+save_as_index!(KnockerTies, "knocker ties rule");
+
+impl Save for CardPoints {
+    fn save(&self, writer: &mut Writer) {
+        for &points in self.0.iter() {
+            writer.u8(points);
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let mut points = [0; 13];
+
+        for slot in points.iter_mut() {
+            *slot = reader.u8()?;
+
+            if *slot == 0 || *slot > MAX_CARD_POINTS {
+                return Err(Invalid("card points"));
+            }
+        }
+
+        Ok(CardPoints(points))
+    }
+}
+
+impl Save for Ruleset {
+    fn save(&self, writer: &mut Writer) {
+        writer.bool(self.knock_on_first_round);
+        self.knocker_ties.save(writer);
+        writer.u32(self.knocker_penalty);
+        writer.bool(self.blitz_on_deal);
+        writer.bool(self.blitz_everyone_loses);
+        writer.bool(self.hidden_31);
+        writer.u32(self.hand_size as u32);
+        writer.u32(self.cards_scored as u32);
+        writer.u32(self.decks as u32);
+        self.card_points.save(writer);
+        writer.bool(self.jokers);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let knock_on_first_round = reader.bool()?;
+        let knocker_ties = KnockerTies::load(reader)?;
+        let knocker_penalty = reader.u32()?;
+        let blitz_on_deal = reader.bool()?;
+        let blitz_everyone_loses = reader.bool()?;
+        let hidden_31 = reader.bool()?;
+        let hand_size = reader.u32()? as usize;
+        let cards_scored = reader.u32()? as usize;
+
+        if hand_size < MIN_HAND_SIZE || hand_size > MAX_HAND_SIZE ||
+           cards_scored < MIN_HAND_SIZE || cards_scored > hand_size {
+            return Err(Invalid("hand size"));
+        }
+
+        let decks = reader.u32()? as usize;
+
+        if decks < 1 || decks > MAX_DECKS {
+            return Err(Invalid("number of decks"));
+        }
+
+        Ok(Ruleset {
+               knock_on_first_round,
+               knocker_ties,
+               knocker_penalty,
+               blitz_on_deal,
+               blitz_everyone_loses,
+               hidden_31,
+               hand_size,
+               cards_scored,
+               decks,
+               card_points: CardPoints::load(reader)?,
+               jokers: reader.bool()?,
+           })
+    }
+}
+
+impl Save for Mode {
+    fn save(&self, writer: &mut Writer) {
+        match *self {
+            Mode::Casual => writer.u8(0),
+            Mode::Match(ref lives) => {
+                writer.u8(1);
+                lives.save(writer);
+            }
+            Mode::Practice => writer.u8(2),
+            Mode::Tutorial(step, strayed) => {
+                writer.u8(3);
+                writer.u32(step as u32);
+                writer.bool(strayed);
+            }
+        }
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        match reader.u8()? {
+            0 => Ok(Mode::Casual),
+            1 => Vec::load(reader).map(Mode::Match),
+            2 => Ok(Mode::Practice),
+            3 => Ok(Mode::Tutorial(reader.u32()? as usize, reader.bool()?)),
+            _ => Err(Invalid("mode")),
+        }
+    }
+}
+
+impl Save for Statistics {
+    fn save(&self, writer: &mut Writer) {
+        writer.u32(self.deals_played);
+        writer.u32(self.deals_won);
+        writer.u32(self.thirty_ones);
+        writer.u32(self.matches_played);
+        writer.u32(self.matches_won);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(Statistics {
+               deals_played: reader.u32()?,
+               deals_won: reader.u32()?,
+               thirty_ones: reader.u32()?,
+               matches_played: reader.u32()?,
+               matches_won: reader.u32()?,
+           })
+    }
+}
+
+impl Save for Settings {
+    fn save(&self, writer: &mut Writer) {
+        self.theme.save(writer);
+        self.difficulty.save(writer);
+        self.opponents.map(|n| n as u32).save(writer);
+        self.rules.save(writer);
+        writer.bool(self.show_tracker);
+    }
+    fn load(reader: &mut Reader) -> Result<Self, LoadError> {
+        let theme = Theme::load(reader)?;
+        let difficulty = Difficulty::load(reader)?;
+        let opponents: Option<u32> = Option::load(reader)?;
+
+        if opponents.map_or(false, |n| n < 1 || n as usize > MAX_OPPONENTS) {
+            return Err(Invalid("number of opponents"));
+        }
+
+        Ok(Settings {
+               theme,
+               difficulty,
+               opponents: opponents.map(|n| n as usize),
+               rules: Ruleset::load(reader)?,
+               show_tracker: reader.bool()?,
+           })
+    }
+}
+
+
+//Settings use the same format as states, just in their own file, so they
+//survive a new game as well as a restart.
+pub fn save_settings(settings: &Settings) -> Vec<u8> {
+    let mut writer = Writer::new();
+
+    settings.save(&mut writer);
+
+    writer.into_bytes()
+}
+
+pub fn load_settings(bytes: &[u8]) -> Result<Settings, LoadError> {
+    let mut reader = Reader::new(bytes)?;
+
+    Settings::load(&mut reader)
+}
+
+pub fn save_statistics(statistics: &Statistics) -> Vec<u8> {
+    let mut writer = Writer::new();
+
+    statistics.save(&mut writer);
+
+    writer.into_bytes()
+}
+
+pub fn load_statistics(bytes: &[u8]) -> Result<Statistics, LoadError> {
+    let mut reader = Reader::new(bytes)?;
+
+    Statistics::load(&mut reader)
+}
+
+//`StdRng` cannot be inspected, so we draw a fresh seed from it instead, which
+//is why this needs `&mut`.
+pub fn save_state(state: &mut State) -> Vec<u8> {
+    let mut writer = Writer::new();
+
+    for _ in 0..SEED_LEN {
+        writer.u64(state.rng.gen::<u64>());
+    }
+    writer.bool(state.title_screen);
+    state.mode.save(&mut writer);
+    state.deck.save(&mut writer);
+    state.pile.save(&mut writer);
+    state.player.save(&mut writer);
+    state.cpu_players.save(&mut writer);
+    state.dealer.save(&mut writer);
+    state.rules.save(&mut writer);
+    state.turn.save(&mut writer);
+    writer.u32(state.turn_count);
+    writer.u32(state.round);
+    writer.str(&state.summary);
+    state.cpu_last_actions.save(&mut writer);
+    state.settings.save(&mut writer);
+    state.statistics.save(&mut writer);
+
+    writer.into_bytes()
+}
+
+pub fn load_state(bytes: &[u8]) -> Result<State, LoadError> {
+    let mut reader = Reader::new(bytes)?;
+
+    let mut seed = Vec::new();
+    for _ in 0..SEED_LEN {
+        seed.push(reader.u64()? as usize);
+    }
+
+    let title_screen = reader.bool()?;
+    let mode = Mode::load(&mut reader)?;
+    let deck = Vec::load(&mut reader)?;
+    let pile = Vec::load(&mut reader)?;
+    let player = HandEnum::load(&mut reader)?;
+    let cpu_players: Vec<HandEnum> = Vec::load(&mut reader)?;
+    let dealer = Participant::load(&mut reader)?;
+    let rules = Ruleset::load(&mut reader)?;
+    let turn = Turn::load(&mut reader)?;
+    let turn_count = reader.u32()?;
+    let round = reader.u32()?;
+    let summary = reader.str()?;
+    let cpu_last_actions = Vec::load(&mut reader)?;
+    let settings = Settings::load(&mut reader)?;
+    let statistics = Statistics::load(&mut reader)?;
+
+    let state = State {
+        rng: StdRng::from_seed(&seed[..]),
+        title_screen,
+        mode,
+        deck,
+        pile,
+        player,
+        cpu_players,
+        dealer,
+        rules,
+        turn,
+        turn_count,
+        round,
+        summary,
+        cpu_last_actions,
+        animations: Vec::new(),
+        odds: None,
+        settings,
+        statistics,
+        dialog: None,
+        ui_context: UIContext::new(),
+    };
+
+    check_seats(&state)?;
+
+    Ok(state)
+}
+
+//Each field can load fine on its own and still not fit with the others, say
+//a damaged file with a turn for a cpu who isn't there, which would otherwise
+//only show up as a panic partway through the deal.
+fn check_seats(state: &State) -> Result<(), LoadError> {
+    let cpu_count = state.cpu_players.len();
+    let seated = |participant: &Participant| match *participant {
+        Player => true,
+        Cpu(i) => i < cpu_count,
+    };
+
+    if state.cpu_last_actions.len() != cpu_count {
+        return Err(Invalid("list of cpu actions"));
+    }
+
+    if let Mode::Match(ref lives) = state.mode {
+        if lives.len() != cpu_count + 1 {
+            return Err(Invalid("list of lives"));
+        }
+    }
+
+    if !seated(&state.dealer) {
+        return Err(Invalid("dealer"));
+    }
+
+    let ending_seated = |kw: &Option<KnockerOrWinner>| match *kw {
+        Some(KnockerOrWinner::Knocker(ref p)) |
+        Some(KnockerOrWinner::Winner(ref p)) => seated(p),
+        None => true,
+    };
+
+    let in_range = match state.turn {
+        Turn::PlayerTurn(ref knocker) |
+        Turn::PlayerSelected(_, ref knocker) => knocker.as_ref().map_or(true, &seated),
+        Turn::CpuTurn(ref kw, i) => ending_seated(kw) && i < cpu_count,
+        Turn::CpuSummary(ref kw) |
+        Turn::Resolution(ref kw) => ending_seated(kw),
+    };
+
+    if in_range {
+        Ok(())
+    } else {
+        Err(Invalid("turn"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the magic, the version and the seed, which is drawn afresh each save.
+    const HEADER_LEN: usize = 8 + SEED_LEN * 8;
+
+    fn hand(cards: &[(Value, Suit)]) -> HandEnum {
+        Hand(cards.iter().map(|&(value, suit)| Card { suit, value }).collect())
+    }
+
+    //Everything set to something other than its default, as far as possible.
+    fn settings() -> Settings {
+        Settings {
+            theme: Theme::all_values()[1],
+            difficulty: Difficulty::Hard,
+            opponents: Some(2),
+            rules: Ruleset {
+                knock_on_first_round: false,
+                knocker_ties: KnockerTies::ForKnocker,
+                knocker_penalty: 3,
+                blitz_on_deal: true,
+                blitz_everyone_loses: true,
+                hidden_31: true,
+                hand_size: 4,
+                cards_scored: 3,
+                decks: 2,
+                card_points: CardPoints::ace_low(),
+                jokers: true,
+            },
+            show_tracker: true,
+        }
+    }
+
+    fn statistics() -> Statistics {
+        Statistics {
+            deals_played: 10,
+            deals_won: 4,
+            thirty_ones: 1,
+            matches_played: 3,
+            matches_won: 2,
+        }
+    }
+
+    fn state() -> State {
+        let seed: &[_] = &[42];
+        let settings = settings();
+
+        State {
+            rng: StdRng::from_seed(seed),
+            title_screen: false,
+            mode: Mode::Match(vec![3, 0, 2]),
+            deck: shoe(1, true),
+            pile: vec![Card { suit: Hearts, value: Two }],
+            player: hand(&[(Ace, Spades), (Joker, Hearts), (Ten, Clubs), (Four, Diamonds)]),
+            cpu_players: vec![hand(&[(King, Clubs), (Queen, Clubs), (Five, Clubs), (Two, Spades)]),
+                              hand(&[(Ten, Hearts), (Two, Spades), (Three, Diamonds),
+                                     (Nine, Hearts)])],
+            dealer: Cpu(1),
+            rules: settings.rules.clone(),
+            turn: Turn::CpuTurn(Some(KnockerOrWinner::Knocker(Player)), 1),
+            turn_count: 7,
+            round: 2,
+            summary: "Cpu 0 drew a card and put a 2 of ♥ back on the pile.\n".to_string(),
+            cpu_last_actions: vec!["drew a card".to_string(), "knocked!".to_string()],
+            animations: Vec::new(),
+            odds: None,
+            settings,
+            statistics: statistics(),
+            dialog: None,
+            ui_context: UIContext::new(),
+        }
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let bytes = save_state(&mut state());
+        let mut loaded = load_state(&bytes).unwrap();
+
+        assert_eq!(loaded.mode, Mode::Match(vec![3, 0, 2]));
+        assert_eq!(loaded.dealer, Cpu(1));
+        assert_eq!(loaded.rules, settings().rules);
+        assert_eq!(loaded.settings, settings());
+        assert_eq!(loaded.statistics, statistics());
+
+        //`State` isn't comparable, but if every field came back the same it
+        //saves the same way again.
+        assert_eq!(&save_state(&mut loaded)[HEADER_LEN..], &bytes[HEADER_LEN..]);
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        assert_eq!(load_settings(&save_settings(&settings())).unwrap(), settings());
+        assert_eq!(load_settings(&save_settings(&Settings::new())).unwrap(),
+                   Settings::new());
+    }
+
+    #[test]
+    fn statistics_round_trip() {
+        assert_eq!(load_statistics(&save_statistics(&statistics())).unwrap(),
+                   statistics());
+    }
+
+    #[test]
+    fn a_truncated_state_does_not_load() {
+        let bytes = save_state(&mut state());
+
+        for len in 0..bytes.len() {
+            assert!(load_state(&bytes[..len]).is_err(), "loaded {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn a_state_from_an_unknown_version_does_not_load() {
+        let mut bytes = save_state(&mut state());
+        bytes[MAGIC.len()] = STATE_VERSION as u8 + 1;
+
+        match load_state(&bytes) {
+            Err(UnknownVersion(version)) => assert_eq!(version, STATE_VERSION + 1),
+            Err(error) => panic!("wrong error: {}", error),
+            Ok(_) => panic!("loaded a state from an unknown version"),
+        }
+    }
+
+    #[test]
+    fn a_state_with_seats_that_do_not_line_up_does_not_load() {
+        let mut state = state();
+        state.turn = Turn::CpuTurn(None, 2);
+
+        match load_state(&save_state(&mut state)) {
+            Err(Invalid("turn")) => {}
+            Err(error) => panic!("wrong error: {}", error),
+            Ok(_) => panic!("loaded a turn for a cpu who isn't there"),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//`bear_lib_terminal` and `common` each have their own copies of these types,
+//(see the note in `common`,) so both sides of every conversion are foreign
+//here and `From` can't be implemented. This trait fills the same role.
+//Every enum is matched exhaustively in both directions, so if either side
+//adds, removes or reorders a variant we find out at compile time instead of
+//getting a different key.
+pub trait Bridge<T> {
+    fn bridge(self) -> T;
+}
+
+use bear_lib_terminal::terminal::{Event, KeyCode};
+use bear_lib_terminal::Color;
+use bear_lib_terminal::geometry::{Point, Rect, Size};
+
+use common;
+use common::KeyCode as CommonKeyCode;
+
+macro_rules! bridge_both_ways {
+    ($a: ty, $b: ty, |$x: ident| $a_to_b: expr, |$y: ident| $b_to_a: expr) => {
+        impl Bridge<$b> for $a {
+            fn bridge(self) -> $b {
+                let $x = self;
+                $a_to_b
+            }
+        }
+
+        impl Bridge<$a> for $b {
+            fn bridge(self) -> $a {
+                let $y = self;
+                $b_to_a
+            }
+        }
+    }
+}
+
+//`$all` lists every variant of `$a`, so the tests can try each one.
+macro_rules! bridge_unit_enum {
+    ($a: ident, $b: ident, $all: ident, $($variant: ident),*) => {
+        #[cfg(test)]
+        const $all: &'static [$a] = &[$($a::$variant),*];
+
+        impl Bridge<$b> for $a {
+            fn bridge(self) -> $b {
+                match self {
+                    $($a::$variant => $b::$variant,)*
+                }
+            }
+        }
+
+        impl Bridge<$a> for $b {
+            fn bridge(self) -> $a {
+                match self {
+                    $($b::$variant => $a::$variant,)*
+                }
+            }
+        }
+    }
+}
+
+bridge_both_ways!(Point,
+                  common::Point,
+                  |p| common::Point { x: p.x, y: p.y },
+                  |p| Point { x: p.x, y: p.y });
+
+bridge_both_ways!(Size,
+                  common::Size,
+                  |s| {
+                      common::Size {
+                          width: s.width,
+                          height: s.height,
+                      }
+                  },
+                  |s| {
+                      Size {
+                          width: s.width,
+                          height: s.height,
+                      }
+                  });
+
+bridge_both_ways!(Rect,
+                  common::Rect,
+                  |r| {
+                      common::Rect {
+                          top_left: r.top_left.bridge(),
+                          top_right: r.top_right.bridge(),
+                          bottom_right: r.bottom_right.bridge(),
+                          bottom_left: r.bottom_left.bridge(),
+                          size: r.size.bridge(),
+                      }
+                  },
+                  |r| {
+                      Rect {
+                          top_left: r.top_left.bridge(),
+                          top_right: r.top_right.bridge(),
+                          bottom_right: r.bottom_right.bridge(),
+                          bottom_left: r.bottom_left.bridge(),
+                          size: r.size.bridge(),
+                      }
+                  });
+
+bridge_both_ways!(Color,
+                  common::Color,
+                  |c| {
+                      common::Color {
+                          red: c.red,
+                          green: c.green,
+                          blue: c.blue,
+                          alpha: c.alpha,
+                      }
+                  },
+                  |c| Color::from_rgba(c.red, c.green, c.blue, c.alpha));
+
+bridge_unit_enum!(KeyCode,
+                  CommonKeyCode,
+                  ALL_KEY_CODES,
+              A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U,
+              V, W, X, Y, Z, Row1, Row2, Row3, Row4, Row5, Row6, Row7, Row8,
+              Row9, Row0, Grave, Minus, Equals, LeftBracket, RightBracket, Backslash,
+              Semicolon, Apostrophe, Comma, Period, Slash, F1, F2, F3, F4, F5,
+              F6, F7, F8, F9, F10, F11, F12, Enter, Escape, Backspace, Tab, Space,
+              Pause, Insert, Home, PageUp, Delete, End, PageDown, Right, Left,
+              Down, Up, NumDivide, NumMultiply, NumMinus, NumPlus, NumEnter,
+              NumPeriod, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+              Num0, MouseLeft, MouseRight, MouseMiddle, MouseFourth, MouseFifth);
+
+bridge_both_ways!(Event,
+                  common::Event,
+                  |e| match e {
+                      Event::Close => common::Event::Close,
+                      Event::Resize { width, height } => {
+                          common::Event::Resize {
+                              width: width,
+                              height: height,
+                          }
+                      }
+                      Event::MouseMove { x, y } => common::Event::MouseMove { x: x, y: y },
+                      Event::MouseScroll { delta } => common::Event::MouseScroll { delta: delta },
+                      Event::KeyPressed { key, ctrl, shift } => {
+                          common::Event::KeyPressed {
+                              key: key.bridge(),
+                              ctrl: ctrl,
+                              shift: shift,
+                          }
+                      }
+                      Event::KeyReleased { key, ctrl, shift } => {
+                          common::Event::KeyReleased {
+                              key: key.bridge(),
+                              ctrl: ctrl,
+                              shift: shift,
+                          }
+                      }
+                      Event::ShiftPressed => common::Event::ShiftPressed,
+                      Event::ShiftReleased => common::Event::ShiftReleased,
+                      Event::ControlPressed => common::Event::ControlPressed,
+                      Event::ControlReleased => common::Event::ControlReleased,
+                  },
+                  |e| match e {
+                      common::Event::Close => Event::Close,
+                      common::Event::Resize { width, height } => {
+                          Event::Resize {
+                              width: width,
+                              height: height,
+                          }
+                      }
+                      common::Event::MouseMove { x, y } => Event::MouseMove { x: x, y: y },
+                      common::Event::MouseScroll { delta } => Event::MouseScroll { delta: delta },
+                      common::Event::KeyPressed { key, ctrl, shift } => {
+                          Event::KeyPressed {
+                              key: key.bridge(),
+                              ctrl: ctrl,
+                              shift: shift,
+                          }
+                      }
+                      common::Event::KeyReleased { key, ctrl, shift } => {
+                          Event::KeyReleased {
+                              key: key.bridge(),
+                              ctrl: ctrl,
+                              shift: shift,
+                          }
+                      }
+                      common::Event::ShiftPressed => Event::ShiftPressed,
+                      common::Event::ShiftReleased => Event::ShiftReleased,
+                      common::Event::ControlPressed => Event::ControlPressed,
+                      common::Event::ControlReleased => Event::ControlReleased,
+                  });
+
+impl<T, U: Bridge<T>> Bridge<Option<T>> for Option<U> {
+    fn bridge(self) -> Option<T> {
+        self.map(Bridge::bridge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fmt::Debug;
+
+    fn round_trips<A, B>(a: A)
+        where A: Bridge<B> + Copy + PartialEq + Debug,
+              B: Bridge<A> + Copy + PartialEq + Debug
+    {
+        let b: B = a.bridge();
+        let back: A = b.bridge();
+        assert_eq!(back, a);
+
+        let again: B = back.bridge();
+        assert_eq!(again, b);
+    }
+
+    #[test]
+    fn every_key_code_round_trips() {
+        for &key in ALL_KEY_CODES.iter() {
+            round_trips::<KeyCode, CommonKeyCode>(key);
+        }
+    }
+
+    #[test]
+    fn key_codes_keep_their_names() {
+        for &key in ALL_KEY_CODES.iter() {
+            let common_key: CommonKeyCode = key.bridge();
+
+            assert_eq!(format!("{:?}", common_key), format!("{:?}", key));
+        }
+    }
+
+    #[test]
+    fn every_event_round_trips() {
+        let events = vec![Event::Close,
+                          Event::Resize {
+                              width: 80,
+                              height: 30,
+                          },
+                          Event::MouseMove { x: 12, y: 34 },
+                          Event::MouseScroll { delta: -3 },
+                          Event::ShiftPressed,
+                          Event::ShiftReleased,
+                          Event::ControlPressed,
+                          Event::ControlReleased];
+
+        for event in events {
+            round_trips::<Event, common::Event>(event);
+        }
+
+        for &key in ALL_KEY_CODES.iter() {
+            for &(ctrl, shift) in [(false, false), (true, false), (false, true), (true, true)]
+                    .iter() {
+                round_trips::<Event, common::Event>(Event::KeyPressed {
+                                                        key: key,
+                                                        ctrl: ctrl,
+                                                        shift: shift,
+                                                    });
+                round_trips::<Event, common::Event>(Event::KeyReleased {
+                                                        key: key,
+                                                        ctrl: ctrl,
+                                                        shift: shift,
+                                                    });
+            }
+        }
+    }
+
+    #[test]
+    fn geometry_round_trips() {
+        for &(x, y) in [(0, 0), (1, 2), (79, 29), (i32::max_value(), 7)].iter() {
+            round_trips::<Point, common::Point>(Point::new(x, y));
+            round_trips::<Size, common::Size>(Size::new(x, y));
+        }
+
+        for &(x, y, width, height) in [(0, 0, 0, 0), (3, 4, 10, 8), (12, 10, 68, 20)].iter() {
+            round_trips::<Rect, common::Rect>(Rect::from_values(x, y, width, height));
+        }
+
+        let common_rect = common::Rect::from_values(5, 6, 7, 8);
+        let rect: Rect = common_rect.bridge();
+        assert_eq!(rect, Rect::from_values(5, 6, 7, 8));
+    }
+
+    #[test]
+    fn colors_round_trip() {
+        for &(red, green, blue, alpha) in [(0, 0, 0, 0),
+                                           (255, 255, 255, 255),
+                                           (12, 34, 56, 78),
+                                           (255, 0, 128, 1)]
+                    .iter() {
+            round_trips::<Color, common::Color>(Color::from_rgba(red, green, blue, alpha));
+            round_trips::<common::Color, Color>(common::Color {
+                                                    red,
+                                                    green,
+                                                    blue,
+                                                    alpha,
+                                                });
+        }
+    }
+}
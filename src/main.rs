@@ -7,66 +7,96 @@ extern crate libloading;
 #[cfg(not(debug_assertions))]
 extern crate state_manipulation;
 
+mod bridge;
+
 #[cfg(debug_assertions)]
 use libloading::Library;
 
-use bear_lib_terminal::terminal::{self, config, Event, KeyCode, state};
-use bear_lib_terminal::Color;
-use bear_lib_terminal::geometry::{Point, Rect, Size};
+use bear_lib_terminal::terminal::{self, config, Event, state};
+
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use std::mem;
+use common::abi::{self, Api};
+#[cfg(debug_assertions)]
+use common::abi::{API_SYMBOL, GetApi};
 
 use common::*;
 
+use bridge::Bridge;
+
 #[cfg(debug_assertions)]
 const LIB_PATH: &'static str = "./target/debug/libstate_manipulation.so";
 #[cfg(not(debug_assertions))]
 const LIB_PATH: &'static str = "Hopefully compiled out";
 
+const FRAME_DELAY_MS: i32 = 16;
+
 #[cfg(debug_assertions)]
 struct Application {
-    library: Library,
+    //`api` points into this, so it needs to live exactly as long.
+    _library: Library,
+    api: Api,
 }
 #[cfg(not(debug_assertions))]
-struct Application {}
+struct Application {
+    api: Api,
+}
 
 #[cfg(debug_assertions)]
 impl Application {
-    fn new() -> Self {
-        let library = Library::new(LIB_PATH).unwrap_or_else(|error| panic!("{}", error));
+    fn new() -> Result<Self, String> {
+        //We load a uniquely named copy so that the dynamic loader doesn't hand
+        //us back the already loaded library, and so the old one can keep
+        //running until the new one has been checked.
+        let copy_path = format!("{}.{}-{}",
+                                LIB_PATH,
+                                std::process::id(),
+                                COPY_COUNT.fetch_add(1, Ordering::SeqCst));
 
-        Application { library: library }
-    }
+        std::fs::copy(LIB_PATH, &copy_path).map_err(|error| error.to_string())?;
 
-    fn new_state(&self, size: common::Size) -> State {
-        unsafe {
-            let f = self.library.get::<fn(common::Size) -> State>(b"new_state\0").unwrap();
+        let library = Library::new(&copy_path);
 
-            f(size)
-        }
-    }
+        //once it's loaded (or failed to) we don't need the file anymore.
+        let _ = std::fs::remove_file(&copy_path);
 
-    fn update_and_render(&self,
-                         platform: &Platform,
-                         state: &mut State,
-                         events: &Vec<Event>)
-                         -> bool {
-        unsafe {
-            let f = self.library
-                .get::<fn(&Platform, &mut State, &Vec<Event>) -> bool>(b"update_and_render\0")
-                .unwrap();
-            f(platform, state, events)
-        }
+        let library = library.map_err(|error| error.to_string())?;
+
+        let api = unsafe {
+            let get_api = library.get::<GetApi>(API_SYMBOL).map_err(|error| error.to_string())?;
+
+            let api = get_api();
+
+            if api.is_null() {
+                return Err("library returned a null api".to_string());
+            }
+
+            *api
+        };
+
+        abi::check(&api)?;
+
+        Ok(Application {
+               _library: library,
+               api,
+           })
     }
 }
 #[cfg(not(debug_assertions))]
 impl Application {
-    fn new() -> Self {
-        Application {}
+    fn new() -> Result<Self, String> {
+        let api = unsafe { *state_manipulation::state_manipulation_api() };
+
+        abi::check(&api)?;
+
+        Ok(Application { api })
     }
+}
 
-    fn new_state(&self, size: common::Size) -> State {
-        state_manipulation::new_state(size)
+impl Application {
+    fn new_state(&self, size: common::Size) -> Box<State> {
+        (self.api.new_state)(size)
     }
 
     fn update_and_render(&self,
@@ -74,9 +104,35 @@ impl Application {
                          state: &mut State,
                          events: &Vec<Event>)
                          -> bool {
-        let mut new_events: Vec<common::Event> =
-            unsafe { events.iter().map(|a| mem::transmute::<Event, common::Event>(*a)).collect() };
-        state_manipulation::update_and_render(platform, state, &mut new_events)
+        let mut new_events: Vec<common::Event> = events.iter().map(|&e| e.bridge()).collect();
+        (self.api.update_and_render)(platform, state, &mut new_events)
+    }
+
+    fn save_state(&self, state: Box<State>) -> Vec<u8> {
+        (self.api.save_state)(state)
+    }
+
+    fn load_state(&self, size: common::Size, saved: &[u8]) -> Box<State> {
+        (self.api.load_state)(size, saved)
+    }
+
+    fn drop_state(&self, state: Box<State>) {
+        (self.api.drop_state)(state)
+    }
+}
+
+#[cfg(debug_assertions)]
+static COPY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn load_application() -> Application {
+    loop {
+        match Application::new() {
+            Ok(app) => return app,
+            Err(error) => {
+                println!("Could not load {}: {}. Retrying.", LIB_PATH, error);
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        }
     }
 }
 
@@ -94,7 +150,7 @@ fn main() {
 
 
 
-    let mut app = Application::new();
+    let mut app = load_application();
 
     let mut state = app.new_state(size());
 
@@ -158,47 +214,67 @@ fn main() {
 
         terminal::refresh();
 
+        //animations are timed in frames, so keep them at a steady rate.
+        terminal::delay(FRAME_DELAY_MS);
+
         if cfg!(debug_assertions) {
             if let Ok(Ok(modified)) = std::fs::metadata(LIB_PATH).map(|m| m.modified()) {
                 if modified > last_modified {
-                    drop(app);
-                    app = Application::new();
-                    last_modified = modified;
+                    match Application::new() {
+                        Ok(new_app) => {
+                            //The old library serializes the state and the new
+                            //one migrates it into whatever its `State` looks
+                            //like now. See `common::save`.
+                            state = new_app.load_state(size(), &app.save_state(state));
+                            app = new_app;
+                            last_modified = modified;
+                        }
+                        Err(error) => {
+                            //most likely the library is still being written,
+                            //so we keep the old one and only try again once
+                            //the file changes, rather than copying and
+                            //loading the same bad library every frame.
+                            println!("Could not reload {}: {}. Waiting for it to change.",
+                                     LIB_PATH,
+                                     error);
+                            last_modified = modified;
+                        }
+                    }
                 }
             }
         }
 
     }
 
+    app.drop_state(state);
+
     terminal::close();
 }
 
 fn clear(area: Option<common::Rect>) {
-    unsafe { terminal::clear(mem::transmute::<Option<common::Rect>, Option<Rect>>(area)) };
+    terminal::clear(area.bridge());
 }
 
 fn size() -> common::Size {
-    unsafe { mem::transmute::<Size, common::Size>(state::size()) }
+    state::size().bridge()
 }
 
 fn mouse_position() -> common::Point {
-    unsafe { mem::transmute::<Point, common::Point>(state::mouse::position()) }
+    state::mouse::position().bridge()
 }
 
 //Note: index selects a cell in *a single* layer, in case you have composition mode on.
 //To pick on different layers, set the current layer then pick.
 fn pick(point: common::Point, index: i32) -> char {
-    terminal::pick(unsafe { mem::transmute::<common::Point, Point>(point) },
-                   index)
+    terminal::pick(point.bridge(), index)
 }
 
 fn key_pressed(key: common::KeyCode) -> bool {
-    terminal::state::key_pressed(unsafe { mem::transmute::<common::KeyCode, KeyCode>(key) })
+    terminal::state::key_pressed(key.bridge())
 }
 
 fn set_colors(fg: common::Color, bg: common::Color) {
-    terminal::set_colors(unsafe { mem::transmute::<common::Color, Color>(fg) },
-                         unsafe { mem::transmute::<common::Color, Color>(bg) });
+    terminal::set_colors(fg.bridge(), bg.bridge());
 
 }
 
@@ -207,14 +283,14 @@ fn get_colors() -> (common::Color, common::Color) {
 }
 
 fn set_foreground(fg: common::Color) {
-    terminal::set_foreground(unsafe { mem::transmute::<common::Color, Color>(fg) });
+    terminal::set_foreground(fg.bridge());
 }
 fn get_foreground() -> common::Color {
-    unsafe { mem::transmute::<Color, common::Color>(terminal::state::foreground()) }
+    terminal::state::foreground().bridge()
 }
 fn set_background(bg: common::Color) {
-    terminal::set_background(unsafe { mem::transmute::<common::Color, Color>(bg) })
+    terminal::set_background(bg.bridge())
 }
 fn get_background() -> common::Color {
-    unsafe { mem::transmute::<Color, common::Color>(terminal::state::background()) }
+    terminal::state::background().bridge()
 }
@@ -0,0 +1,159 @@
+use common::*;
+use common::Spot::*;
+
+use layers::{self, Layer};
+use layout::{self, Layout};
+use theme::Palette;
+
+use super::{draw_card_sized, draw_card_back_sized, SEAT_CARD_WIDTH, SEAT_CARD_HEIGHT,
+            SEAT_CARD_OFFSET};
+
+pub const MOVE_FRAMES: u32 = 12;
+//how long a cpu "thinks" before moving, so you can see whose turn it is.
+pub const THINK_FRAMES: u32 = 15;
+
+//Queues a card moving from `from` to `to`, starting `delay` frames after
+//everything already queued has finished, so moves play out one at a time.
+pub fn animate(state: &mut State, card: Option<Card>, from: Spot, to: Spot, delay: u32) {
+    animate_parts(&mut state.animations,
+                  state.ui_context.frame,
+                  card,
+                  from,
+                  to,
+                  delay)
+}
+
+pub fn animate_parts(animations: &mut Vec<Tween>,
+                     frame: u32,
+                     card: Option<Card>,
+                     from: Spot,
+                     to: Spot,
+                     delay: u32) {
+    let start_frame = animations
+        .iter()
+        .map(Tween::end_frame)
+        .fold(frame, std::cmp::max) + delay;
+
+    animations.push(Tween {
+                        card,
+                        from,
+                        to,
+                        start_frame,
+                        duration: MOVE_FRAMES,
+                    });
+}
+
+//Returns true if anything is still moving. Input should be ignored until this
+//returns false.
+pub fn update(state: &mut State) -> bool {
+    let frame = state.ui_context.frame;
+
+    state.animations.retain(|tween| !tween.is_done(frame));
+
+    !state.animations.is_empty()
+}
+
+//true if a card is on its way to `spot` but hasn't arrived, in which case
+//whatever is there shouldn't be drawn yet.
+pub fn is_arriving(state: &State, spot: Spot) -> bool {
+    state.animations.iter().any(|tween| tween.to == spot)
+}
+
+//The pile as it should look right now, which lags behind `state.pile` until
+//the cards moving to and from it get where they are going.
+pub fn displayed_pile_top(state: &State) -> Option<Card> {
+    let frame = state.ui_context.frame;
+
+    let still_on_pile = state
+        .animations
+        .iter()
+        .filter(|tween| tween.from == Pile && !tween.has_started(frame))
+        .filter_map(|tween| tween.card.clone())
+        .next();
+
+    if still_on_pile.is_some() {
+        return still_on_pile;
+    }
+
+    let arriving = state
+        .animations
+        .iter()
+        .filter(|tween| tween.to == Pile)
+        .count();
+
+    if arriving < state.pile.len() {
+        state.pile.get(state.pile.len() - 1 - arriving).cloned()
+    } else {
+        None
+    }
+}
+
+//the seat of whichever cpu is currently moving cards around, if any.
+pub fn moving_seat(state: &State) -> Option<usize> {
+    state
+        .animations
+        .iter()
+        .filter_map(|tween| match (tween.from, tween.to) {
+                        (Seat(i), _) | (_, Seat(i)) => Some(i),
+                        _ => None,
+                    })
+        .next()
+}
+
+fn spot_rect(spot: Spot, state: &State, layout: &Layout) -> Rect {
+    let card = layout.card;
+
+    match spot {
+        Deck => Rect::from_values(layout.deck.x, layout.deck.y, card.width, card.height),
+        Pile => Rect::from_values(layout.pile.x, layout.pile.y, card.width, card.height),
+        Selected => {
+            Rect::from_values(layout.selected.x, layout.selected.y, card.width, card.height)
+        }
+        PlayerHand(i) => {
+            let position = layout.hand_card(i as i32);
+
+            Rect::from_values(position.x, position.y, card.width, card.height)
+        }
+        Seat(i) => {
+            let seat = layout.seat(i as i32, state.cpu_players.len() as i32);
+            let x = seat.top_left.x + SEAT_CARD_OFFSET;
+
+            if layout::seat_shows_cards(&seat) {
+                Rect::from_values(x, seat.top_left.y + 1, SEAT_CARD_WIDTH, SEAT_CARD_HEIGHT)
+            } else {
+                //the cards are only listed, so it shrinks down over the list
+                //rather than spill into the next row of seats.
+                Rect::from_values(x, seat.top_left.y, SEAT_CARD_WIDTH, 2)
+            }
+        }
+    }
+}
+
+fn lerp(from: i32, to: i32, t: f32) -> i32 {
+    from + ((to - from) as f32 * t).round() as i32
+}
+
+//Moving cards go over everything else on the table.
+pub fn draw(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    layers::with_layer(platform, Layer::Raised, || draw_tweens(platform, palette, state, layout));
+}
+
+fn draw_tweens(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    let frame = state.ui_context.frame;
+
+    for tween in state.animations.iter().filter(|tween| tween.has_started(frame)) {
+        let from = spot_rect(tween.from, state, layout);
+        let to = spot_rect(tween.to, state, layout);
+        let t = tween.progress(frame);
+
+        let x = lerp(from.top_left.x, to.top_left.x, t);
+        let y = lerp(from.top_left.y, to.top_left.y, t);
+        let w = lerp(from.size.width, to.size.width, t);
+        let h = lerp(from.size.height, to.size.height, t);
+
+        match tween.card {
+            Some(ref card) => draw_card_sized(platform, palette, x, y, w, h, card),
+            None => draw_card_back_sized(platform, palette, x, y, w, h),
+        }
+    }
+}
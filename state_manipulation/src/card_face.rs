@@ -0,0 +1,122 @@
+use common::*;
+
+use theme::{self, Palette};
+
+//cards at least this big get the full face, pips and all
+pub const FULL_WIDTH: i32 = 16;
+pub const FULL_HEIGHT: i32 = 12;
+
+//Where the pips go on a grid three columns wide and seven rows tall, roughly
+//the way they are laid out on real cards.
+const PIPS: [&'static [(i32, i32)]; 9] =
+    [&[(1, 0), (1, 6)],
+     &[(1, 0), (1, 3), (1, 6)],
+     &[(0, 0), (2, 0), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (1, 3), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (0, 3), (2, 3), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (1, 1), (0, 3), (2, 3), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (1, 1), (0, 3), (2, 3), (1, 5), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (0, 2), (2, 2), (1, 3), (0, 4), (2, 4), (0, 6), (2, 6)],
+     &[(0, 0), (2, 0), (1, 1), (0, 2), (2, 2), (0, 4), (2, 4), (1, 5), (0, 6), (2, 6)]];
+
+//`*` is replaced with the suit.
+const ACE_ART: &'static [&'static str] = &["   ^   ",
+                                          "  / \\  ",
+                                          " /   \\ ",
+                                          "<  *  >",
+                                          " \\   / ",
+                                          "  \\ /  ",
+                                          "   v   "];
+const JACK_ART: &'static [&'static str] = &[" ____ ", "|*  *|", "|o  o|", "| -- |", " \\__/ "];
+const QUEEN_ART: &'static [&'static str] = &[" .^^. ", "(*  *)", "(o  o)", "( -- )", " \\__/ "];
+const KING_ART: &'static [&'static str] = &["|\\/\\/|", "|*  *|", "|o  o|", "| == |", "\\____/"];
+const JOKER_ART: &'static [&'static str] = &["\\/\\/\\/", "(o  o)", "( \\/ )", " \\__/ "];
+
+//Jokers don't show a suit, only their colour.
+pub fn suit_text(card: &Card) -> String {
+    if card.is_joker() {
+        String::new()
+    } else {
+        card.suit.to_string()
+    }
+}
+
+//Draws everything inside the border of a `w` by `h` card at `x`, `y`. Smaller
+//cards get less detail, down to just the corner index.
+pub fn draw(platform: &Platform, palette: &Palette, x: i32, y: i32, w: i32, h: i32, card: &Card) {
+    theme::with_colors(platform, palette.suit(card.suit), palette.card_face, || {
+        if w >= FULL_WIDTH && h >= FULL_HEIGHT {
+            draw_full(platform, x, y, w, h, card);
+        } else {
+            draw_compact(platform, x, y, w, h, card);
+        }
+    });
+}
+
+fn draw_full(platform: &Platform, x: i32, y: i32, w: i32, h: i32, card: &Card) {
+    let value = card.value.to_string();
+    let suit = suit_text(card);
+
+    //corner indices, the bottom right one upside down as it were.
+    (platform.print_xy)(x + 1, y + 1, &value);
+    (platform.print_xy)(x + 1, y + 2, &suit);
+
+    let right = x + w - 1;
+    let bottom = y + h - 1;
+    (platform.print_xy)(right - 1, bottom - 2, &suit);
+    (platform.print_xy)(right - value.chars().count() as i32, bottom - 1, &value);
+
+    let art = match card.value {
+        Value::Ace => Some(ACE_ART),
+        Value::Jack => Some(JACK_ART),
+        Value::Queen => Some(QUEEN_ART),
+        Value::King => Some(KING_ART),
+        Value::Joker => Some(JOKER_ART),
+        _ => None,
+    };
+
+    match art {
+        Some(lines) => {
+            let top = y + (h - lines.len() as i32) / 2;
+
+            for (i, line) in lines.iter().enumerate() {
+                let line = line.replace("*", &suit);
+                let line_x = x + (w - line.chars().count() as i32) / 2;
+
+                (platform.print_xy)(line_x, top + i as i32, &line);
+            }
+        }
+        None => {
+            let pip_count = u8::from(card.value) as usize;
+            let columns = [x + 4, x + (w - 1) / 2, right - 4];
+            let top = y + 2;
+            let span = (bottom - 2) - top;
+
+            for &(column, row) in PIPS[pip_count - 2].iter() {
+                let pip_y = top + (row * span + 3) / 6;
+
+                (platform.print_xy)(columns[column as usize], pip_y, &suit);
+            }
+        }
+    }
+}
+
+fn draw_compact(platform: &Platform, x: i32, y: i32, w: i32, h: i32, card: &Card) {
+    let suit = suit_text(card);
+    let index = format!("{}{}", card.value, suit);
+    let index_len = index.chars().count() as i32;
+    let inner_width = w - 2;
+
+    if inner_width <= 0 || h <= 2 {
+        return;
+    }
+
+    let truncated: String = index.chars().take(inner_width as usize).collect();
+    (platform.print_xy)(x + 1, y + 1, &truncated);
+
+    //only bother with the rest if there's room for it not to overlap.
+    if inner_width >= index_len + 2 && h >= 6 {
+        (platform.print_xy)(x + w - 1 - index_len, y + h - 2, &index);
+        (platform.print_xy)(x + w / 2, y + h / 2, &suit);
+    }
+}
@@ -0,0 +1,221 @@
+use common::*;
+use common::Suit::*;
+use common::Value::*;
+
+use layout::Layout;
+use theme::Palette;
+use ui::{self, Input, MenuAction};
+use ui::MenuItem::*;
+
+const DIALOG_WIDTH: i32 = 56;
+
+//Each page is a title and its text, with the house rules filled in, so what it
+//says is what the game will actually do.
+fn pages(rules: &Ruleset) -> Vec<(&'static str, String)> {
+    let when_to_knock = if rules.knock_on_first_round {
+        "Instead of drawing you can knock, even on your very first turn."
+    } else {
+        "Once everyone has had a turn, you can knock instead of drawing."
+    };
+
+    let ties = match rules.knocker_ties {
+        KnockerTies::Shared => "Ties are shared, win or lose.",
+        KnockerTies::AgainstKnocker => "The knocker loses any tie.",
+        KnockerTies::ForKnocker => "The knocker wins any tie.",
+    };
+
+    let penalty = match rules.knocker_penalty {
+        1 => "a life, like anyone else".to_string(),
+        lives => format!("{} lives", lives),
+    };
+
+    let shoe = if rules.decks > 1 {
+        format!(" It's {} decks shuffled together, so the same card can turn up more \
+                 than once.",
+                number(rules.decks))
+    } else {
+        String::new()
+    };
+
+    let playing = format!("Drawing\n\
+                           On your turn, take either the top card of the pile, which \
+                           everyone can see, or the top card of the deck, which no one \
+                           can.{}\n\
+                           \n\
+                           Discarding\n\
+                           Then put one card back on the pile, either one from your hand \
+                           or the one you just took. You always end a turn with {} cards.",
+                          shoe,
+                          number(rules.hand_size));
+
+    let knocking = format!("{} Everyone else gets one more turn, then the best hand wins \
+                            and the worst loses. Only one person can knock each deal.\n\
+                            \n\
+                            {} A knocker with the worst hand loses {}.",
+                           when_to_knock,
+                           ties,
+                           penalty);
+
+    let when_31_counts = if rules.hidden_31 {
+        "It stays hidden until someone knocks, and then wins the deal."
+    } else {
+        "Getting it wins the deal straight away."
+    };
+    let on_the_deal = if rules.blitz_on_deal {
+        " That includes being dealt it."
+    } else {
+        ""
+    };
+    let who_loses = if rules.blitz_everyone_loses {
+        "Everyone else loses a life."
+    } else {
+        "The worst of the rest loses a life."
+    };
+
+    let counted = if rules.cards_scored < rules.hand_size {
+        format!("its best {} cards", number(rules.cards_scored))
+    } else {
+        "the cards".to_string()
+    };
+    let three_of_a_kind = if rules.has_thirty_and_a_half() {
+        "\n\n30½\nThree cards of the same value score 30½, which beats anything under 31."
+    } else {
+        ""
+    };
+    let jokers = if rules.jokers {
+        "\n\nJokers\nEach deck has two jokers, which count as whatever card makes your hand \
+         best."
+    } else {
+        ""
+    };
+
+    let points = if rules.card_points == CardPoints::ace_high() {
+        "Aces are worth 11, face cards 10 and everything else its number.".to_string()
+    } else if rules.card_points == CardPoints::ace_low() {
+        "Aces are worth 1, face cards 10 and everything else its number.".to_string()
+    } else {
+        let each: Vec<String> = Value::all_values()
+            .into_iter()
+            .map(|value| format!("{} {}", value, rules.card_points.of(value)))
+            .collect();
+
+        format!("The cards are worth {}.", each.join(", "))
+    };
+    let best_hand = if rules.card_points == CardPoints::ace_high() {
+        format!("An ace and {} ten point cards", number(rules.cards_scored - 1))
+    } else {
+        format!("The best {} cards there are", number(rules.cards_scored))
+    };
+
+    let scoring = format!("A hand scores the total of {} in one suit. {}\n\
+                           \n\
+                           {}\n\
+                           {} in the same suit make {}, the best hand there is. {}{} {}{}{}",
+                          counted,
+                          points,
+                          rules.best_score(),
+                          best_hand,
+                          rules.best_score(),
+                          when_31_counts,
+                          on_the_deal,
+                          who_loses,
+                          three_of_a_kind,
+                          jokers);
+
+    vec![("Playing", playing),
+         ("Knocking", knocking),
+         ("Scoring", scoring),
+         ("Examples", examples(rules))]
+}
+
+fn number(n: usize) -> String {
+    match n {
+        2 => "two".to_string(),
+        3 => "three".to_string(),
+        4 => "four".to_string(),
+        n => n.to_string(),
+    }
+}
+
+//Sample hands, scored the same way as the ones on the table.
+fn examples(rules: &Ruleset) -> String {
+    let hands = [[(King, Hearts), (Nine, Hearts), (Four, Clubs)],
+                 [(Ace, Spades), (King, Spades), (Ten, Spades)],
+                 [(Seven, Clubs), (Seven, Diamonds), (Seven, Hearts)],
+                 [(Ace, Diamonds), (Five, Clubs), (Three, Clubs)],
+                 [(Queen, Clubs), (Jack, Clubs), (Eight, Clubs)]];
+
+    let mut text = "How some hands score:\n".to_string();
+
+    for hand in hands.iter() {
+        let cards: Vec<Card> = hand.iter()
+            .map(|&(value, suit)| Card { value, suit })
+            .collect();
+
+        let names: Vec<String> = cards.iter()
+            .map(|card| format!("{}{}", card.value, card.suit))
+            .collect();
+
+        text += &format!("\n  {:<12}{}",
+                         names.join(" "),
+                         score_cards(&cards, rules));
+    }
+
+    text
+}
+
+//Returns true once the player is done with it.
+pub fn do_rules_dialog(platform: &Platform,
+                       context: &mut UIContext,
+                       palette: &Palette,
+                       layout: &Layout,
+                       input: &Input,
+                       rules: &Ruleset,
+                       page: &mut usize)
+                       -> bool {
+    let pages = pages(rules);
+    *page = std::cmp::min(*page, pages.len() - 1);
+
+    //sized for the longest page so flipping through doesn't move the buttons.
+    let line_count = pages
+        .iter()
+        .map(|&(_, ref text)| ui::wrap(text, DIALOG_WIDTH as usize).len() as i32)
+        .max()
+        .unwrap_or(0);
+
+    let (title, ref text) = pages[*page];
+    let items = [Choice("Page".to_string(), title.to_string()), Button("Close".to_string())];
+    let size = Size::new(DIALOG_WIDTH + 4, line_count + 7);
+
+    let used = ui::modal_dialog(platform,
+                                context,
+                                palette,
+                                layout,
+                                "Rules",
+                                size,
+                                |context, rect| {
+        let x = rect.top_left.x + 2;
+        let y = rect.top_left.y + 3;
+
+        ui::label(platform, x, y, DIALOG_WIDTH, text);
+
+        ui::menu(platform,
+                 context,
+                 palette,
+                 input,
+                 Point::new(x, y + line_count + 1),
+                 DIALOG_WIDTH,
+                 &items)
+    });
+
+    match used {
+        Some((0, action)) => {
+            let indices: Vec<usize> = (0..pages.len()).collect();
+            *page = ui::cycle(&indices, page, action);
+
+            false
+        }
+        Some((_, MenuAction::Activate)) => true,
+        _ => false,
+    }
+}
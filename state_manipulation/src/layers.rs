@@ -0,0 +1,85 @@
+use common::*;
+
+use theme;
+
+//Everything is drawn on one of these, and each one is composited over the ones
+//before it, so drawing something on a higher layer never erases what is below.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layer {
+    //the felt, text and ordinary buttons
+    Table,
+    Cards,
+    //hovered and moving cards
+    Raised,
+    Dialog,
+    Tooltip,
+}
+use self::Layer::*;
+
+impl Layer {
+    //Only the bottom terminal layer has background colours, so every other
+    //`Layer` gets two terminal layers: one for what is drawn on it and the one
+    //just below that, where `fill` puts opaque blocks to hide the layers below.
+    fn index(self) -> i32 {
+        match self {
+            Table => 0,
+            Cards => 2,
+            Raised => 4,
+            Dialog => 6,
+            Tooltip => 8,
+        }
+    }
+}
+
+pub fn with_layer<F, R>(platform: &Platform, layer: Layer, draw: F) -> R
+    where F: FnOnce() -> R
+{
+    let old_layer = (platform.get_layer)();
+
+    (platform.set_layer)(layer.index());
+    let result = draw();
+    (platform.set_layer)(old_layer);
+
+    result
+}
+
+//Makes `rect` on the current layer opaque, in `color`.
+pub fn fill(platform: &Platform, rect: Rect, color: Color) {
+    let layer = (platform.get_layer)();
+
+    if layer == Table.index() {
+        theme::with_colors(platform,
+                           color,
+                           color,
+                           || (platform.clear)(Some(rect)));
+    } else {
+        (platform.set_layer)(layer - 1);
+
+        theme::with_foreground(platform, color, || {
+            let row: String = (0..rect.size.width).map(|_| '█').collect();
+
+            for y in rect.top_left.y..rect.top_left.y + rect.size.height {
+                (platform.print_xy)(rect.top_left.x, y, &row);
+            }
+        });
+
+        (platform.set_layer)(layer);
+    }
+}
+
+//Whether anything has been drawn at `point` on `layer` so far this frame.
+pub fn hit_test(platform: &Platform, layer: Layer, point: Point) -> bool {
+    let old_layer = (platform.get_layer)();
+
+    let picks = |index| {
+        (platform.set_layer)(index);
+        (platform.pick)(point, 0) != '\0'
+    };
+
+    let index = layer.index();
+    let hit = picks(index) || (layer != Table && picks(index - 1));
+
+    (platform.set_layer)(old_layer);
+
+    hit
+}
@@ -0,0 +1,161 @@
+use common::*;
+
+use card_face;
+
+use super::{SEAT_CARD_HEIGHT, SEAT_CARD_OFFSET, SEAT_CARD_WIDTH};
+
+//Below this we show a notice instead of drawing cards partly off-screen.
+pub const MIN_WIDTH: i32 = 72;
+pub const MIN_HEIGHT: i32 = 24;
+
+pub const BUTTON_WIDTH: i32 = 11;
+pub const BUTTON_HEIGHT: i32 = 3;
+
+//Below this the cards switch to a smaller style so everything still fits.
+const FULL_CARD_MIN_WIDTH: i32 = 80;
+const FULL_CARD_MIN_HEIGHT: i32 = 30;
+
+const COMPACT_CARD_WIDTH: i32 = 10;
+const COMPACT_CARD_HEIGHT: i32 = 8;
+
+pub const CARD_OFFSET: i32 = 5;
+pub const CARD_OFFSET_DELTA: i32 = 6;
+
+//how much of the hand is visible above the bottom of the window
+const HAND_HEIGHT_OFFSET: i32 = 8;
+
+const DECK_GAP: i32 = 4;
+
+//what a seat needs to show the biggest hand as cards
+const FULL_SEAT_WIDTH: i32 = (MAX_HAND_SIZE as i32 - 1) * SEAT_CARD_OFFSET + SEAT_CARD_WIDTH;
+//and when there are too many seats for that, what it needs to list them
+const COMPACT_SEAT_WIDTH: i32 = 12;
+
+//Everything is positioned relative to the window size, which is recomputed
+//every frame so resizing the window just reflows everything.
+pub struct Layout {
+    pub size: Size,
+    pub too_small: bool,
+    pub card: Size,
+    //the column of buttons down the left side
+    pub buttons: Rect,
+    pub opponents: Rect,
+    pub log: Point,
+    pub pile: Point,
+    pub deck: Point,
+    pub hand: Point,
+    //where the card you just picked up waits while you choose a discard
+    pub selected: Point,
+    //between the hand and the selected card
+    pub score_panel: Rect,
+    //between the table and the hand, where how the deal went is shown
+    pub results: Rect,
+}
+
+impl Layout {
+    pub fn new(size: Size) -> Self {
+        let too_small = size.width < MIN_WIDTH || size.height < MIN_HEIGHT;
+
+        //so we never ask `Point::new` for a negative coordinate.
+        let width = std::cmp::max(size.width, MIN_WIDTH);
+        let height = std::cmp::max(size.height, MIN_HEIGHT);
+
+        let card = if width < FULL_CARD_MIN_WIDTH || height < FULL_CARD_MIN_HEIGHT {
+            Size::new(COMPACT_CARD_WIDTH, COMPACT_CARD_HEIGHT)
+        } else {
+            Size::new(card_face::FULL_WIDTH, card_face::FULL_HEIGHT)
+        };
+
+        let table_y = height / 3;
+
+        let pile_x = std::cmp::min(width / 2, width - 2 * card.width - DECK_GAP);
+
+        let hand = Point::new(CARD_OFFSET, height - HAND_HEIGHT_OFFSET);
+
+        //room for the biggest hand, whatever the rules are this deal.
+        let hand_right = hand.x + (MAX_HAND_SIZE as i32 - 1) * CARD_OFFSET_DELTA + card.width;
+        //over at the right edge, which leaves the score panel as wide as it
+        //can be.
+        let selected_x = std::cmp::max(width - card.width, hand_right + 1);
+        let selected = Point::new(selected_x, hand.y);
+
+        Layout {
+            size,
+            too_small,
+            card,
+            buttons: Rect::from_values(0, 0, BUTTON_WIDTH, table_y),
+            opponents: Rect::from_values(BUTTON_WIDTH + 1,
+                                         0,
+                                         width - (BUTTON_WIDTH + 1),
+                                         table_y - 1),
+            log: Point::new(BUTTON_WIDTH + 1, table_y),
+            pile: Point::new(pile_x, table_y),
+            deck: Point::new(pile_x + card.width + DECK_GAP, table_y),
+            hand,
+            selected,
+            score_panel: Rect::from_values(hand_right + 1,
+                                           hand.y,
+                                           std::cmp::max(selected.x - hand_right - 2, 0),
+                                           HAND_HEIGHT_OFFSET),
+            results: Rect::from_values(BUTTON_WIDTH + 1,
+                                       table_y,
+                                       width - (BUTTON_WIDTH + 1),
+                                       hand.y - table_y),
+        }
+    }
+
+    //the top-left corner of the `index`th button in the left column.
+    pub fn button(&self, index: i32) -> Point {
+        self.buttons.top_left.add(0, index * BUTTON_HEIGHT)
+    }
+
+    //Each opponent gets an equal slice of the strip across the top. When there
+    //are too many of them for one row of seats with room for their cards, they
+    //go in as many rows as it takes, see `seat_shows_cards`.
+    pub fn seat(&self, index: i32, count: i32) -> Rect {
+        let strip = self.opponents;
+        let count = std::cmp::max(count, 1);
+
+        let per_row = if count * FULL_SEAT_WIDTH <= strip.size.width {
+            count
+        } else {
+            std::cmp::max(strip.size.width / COMPACT_SEAT_WIDTH, 1)
+        };
+        let rows = (count + per_row - 1) / per_row;
+
+        let width = strip.size.width / per_row;
+        let height = strip.size.height / rows;
+
+        Rect::from_values(strip.top_left.x + (index % per_row) * width,
+                          strip.top_left.y + (index / per_row) * height,
+                          width,
+                          height)
+    }
+
+    pub fn hand_card(&self, index: i32) -> Point {
+        self.hand.add(index * CARD_OFFSET_DELTA, 0)
+    }
+
+    //dialogs go in the middle of the window, over the table.
+    pub fn dialog(&self, size: Size) -> Rect {
+        Rect::from_values(std::cmp::max((self.size.width - size.width) / 2, 0),
+                          std::cmp::max((self.size.height - size.height) / 2, 0),
+                          size.width,
+                          size.height)
+    }
+}
+
+//Whether `seat` has room to draw the cards in it under the name, rather than
+//just list them.
+pub fn seat_shows_cards(seat: &Rect) -> bool {
+    seat.size.width >= FULL_SEAT_WIDTH && seat.size.height > SEAT_CARD_HEIGHT
+}
+
+pub fn draw_too_small_notice(platform: &Platform, size: Size) {
+    let lines = [format!("The window is too small ({}).", size),
+                 format!("Please make it at least {}.", Size::new(MIN_WIDTH, MIN_HEIGHT))];
+
+    for (i, line) in lines.iter().enumerate() {
+        (platform.print_xy)(0, i as i32, line);
+    }
+}
@@ -3,14 +3,32 @@ extern crate common;
 
 use common::*;
 use common::HandEnum::*;
-use common::HandCard::*;
 use common::Turn::*;
 use common::Participant::*;
 use common::Score::*;
 use common::KnockerOrWinner::*;
 
-
-use std::collections::HashMap;
+mod animation;
+mod card_face;
+mod help;
+mod layers;
+mod layout;
+mod resolution;
+mod saved_game;
+mod score_panel;
+mod settings;
+mod statistics;
+mod theme;
+mod title;
+mod tracker;
+mod turns;
+mod tutorial;
+mod ui;
+use layers::Layer;
+use layout::Layout;
+use theme::Palette;
+use tutorial::Action;
+use ui::Input;
 
 use rand::{StdRng, SeedableRng, Rng};
 
@@ -24,7 +42,6 @@ macro_rules! s {
 //crate is not a dylib. Assuming you make this crate *not* a dylib on release,
 //these configs should work
 #[cfg(debug_assertions)]
-#[no_mangle]
 pub fn new_state(size: Size) -> State {
     //skip the title screen
     println!("debug on");
@@ -32,10 +49,15 @@ pub fn new_state(size: Size) -> State {
     let seed: &[_] = &[42];
     let mut rng: StdRng = SeedableRng::from_seed(seed);
 
-    make_state(size, false, rng)
+    make_state(size,
+               false,
+               Mode::Casual,
+               settings::load(),
+               statistics::load(),
+               None,
+               rng)
 }
 #[cfg(not(debug_assertions))]
-#[no_mangle]
 pub fn new_state(size: Size) -> State {
     //show the title screen
     let timestamp = std::time::SystemTime::now()
@@ -47,18 +69,77 @@ pub fn new_state(size: Size) -> State {
     let seed: &[_] = &[timestamp as usize];
     let rng: StdRng = SeedableRng::from_seed(seed);
 
-    make_state(size, true, rng)
+    make_state(size,
+               true,
+               Mode::Casual,
+               settings::load(),
+               statistics::load(),
+               None,
+               rng)
 }
 
-fn deal(state: &mut State) -> Card {
+static API: common::abi::Api = common::abi::Api {
+    version: common::abi::API_VERSION,
+    abi_hash: common::abi::abi_hash(),
+    new_state: new_boxed_state,
+    update_and_render,
+    save_state,
+    load_state,
+    drop_state,
+};
+
+//This is the only symbol `main` looks up, see `common::abi`.
+#[no_mangle]
+pub extern "C" fn state_manipulation_api() -> *const common::abi::Api {
+    &API
+}
+
+//`main` only ever holds the `State` behind this box and hands it back to us,
+//so that a reloaded library with a different `State` layout never has to
+//interpret memory laid out by the old one.
+pub fn new_boxed_state(size: Size) -> Box<State> {
+    Box::new(new_state(size))
+}
+
+//called on the old library before it is unloaded.
+pub fn save_state(mut state: Box<State>) -> Vec<u8> {
+    common::save::save_state(&mut state)
+}
+
+//called on the new library after it is loaded.
+pub fn load_state(size: Size, saved: &[u8]) -> Box<State> {
+    match common::save::load_state(saved) {
+        Ok(state) => Box::new(state),
+        Err(error) => {
+            println!("Could not migrate the previous state ({}), starting a new game instead.",
+                     error);
+            new_boxed_state(size)
+        }
+    }
+}
+
+pub fn drop_state(state: Box<State>) {
+    drop(state);
+}
+
+//Every card is in someone's hand, so there's nothing left to draw.
+#[derive(Debug)]
+struct OutOfCards;
+
+//`MAX_OPPONENTS` and `MAX_HAND_SIZE` are small enough that one deck always
+//covers the deal itself.
+const DEAL_FITS: &'static str = "there weren't enough cards to deal";
+
+fn deal(state: &mut State) -> Result<Card, OutOfCards> {
     deal_parts(&mut state.deck, &mut state.pile, &mut state.rng)
 }
 
-fn deal_parts(deck: &mut Vec<Card>, pile: &mut Vec<Card>, rng: &mut StdRng) -> Card {
+fn deal_parts(deck: &mut Vec<Card>,
+              pile: &mut Vec<Card>,
+              rng: &mut StdRng)
+              -> Result<Card, OutOfCards> {
     //reshuffle if we run out of cards.
     if deck.len() == 0 {
-        debug_assert!(pile.len() != 0, "deck was empty and so was the pile!");
-
         for card in pile.drain(..) {
             deck.push(card);
         }
@@ -66,85 +147,127 @@ fn deal_parts(deck: &mut Vec<Card>, pile: &mut Vec<Card>, rng: &mut StdRng) -> C
         rng.shuffle(deck.as_mut_slice());
     };
 
-    deck.pop()
-        .unwrap_or(Card {
-                       suit: Suit::Spades,
-                       value: Value::Ace,
-                   })
+    deck.pop().ok_or(OutOfCards)
 }
 
+fn deal_hand(hand_size: usize,
+             deck: &mut Vec<Card>,
+             pile: &mut Vec<Card>,
+             rng: &mut StdRng)
+             -> Result<HandEnum, OutOfCards> {
+    (0..hand_size)
+        .map(|_| deal_parts(deck, pile, rng))
+        .collect::<Result<Vec<Card>, OutOfCards>>()
+        .map(Hand)
+}
+
+fn make_state(size: Size,
+              title_screen: bool,
+              mode: Mode,
+              settings: Settings,
+              statistics: Statistics,
+              last_dealer: Option<Participant>,
+              mut rng: StdRng)
+              -> State {
+    //the tutorial is written for the standard rules.
+    let rules = if let Mode::Tutorial(_, _) = mode {
+        Ruleset::new()
+    } else {
+        settings.rules.clone()
+    };
 
-fn make_state(size: Size, title_screen: bool, mut rng: StdRng) -> State {
-    let mut deck = Card::all_values();
+    let mut deck = if let Mode::Tutorial(_, _) = mode {
+        tutorial::deck()
+    } else {
+        let mut deck = shoe(rules.decks, rules.jokers);
 
-    rng.shuffle(deck.as_mut_slice());
+        rng.shuffle(deck.as_mut_slice());
+
+        deck
+    };
 
     let mut pile = Vec::new();
     let player;
     let mut cpu_players;
+    let cpu_players_count;
     let pile_card = {
         let deck_ref = &mut deck;
         let pile_ref = &mut pile;
         let rng_ref = &mut rng;
 
-        player = Hand(deal_parts(deck_ref, pile_ref, rng_ref),
-                      deal_parts(deck_ref, pile_ref, rng_ref),
-                      deal_parts(deck_ref, pile_ref, rng_ref));
+        player = deal_hand(rules.hand_size, deck_ref, pile_ref, rng_ref).expect(DEAL_FITS);
 
-        let cpu_players_count = rng_ref.gen_range(1, 5);
+        cpu_players_count = match (&mode, settings.opponents) {
+            //the same people keep playing until the match is over
+            (&Mode::Match(ref lives), _) if lives.len() > 0 => lives.len() - 1,
+            (&Mode::Tutorial(_, _), _) => tutorial::OPPONENTS,
+            (_, Some(count)) => count,
+            (_, None) => rng_ref.gen_range(1, MAX_RANDOM_OPPONENTS + 1),
+        };
         cpu_players = Vec::new();
 
         for _ in 0..cpu_players_count {
-            cpu_players.push(Hand(deal_parts(deck_ref, pile_ref, rng_ref),
-                                  deal_parts(deck_ref, pile_ref, rng_ref),
-                                  deal_parts(deck_ref, pile_ref, rng_ref)));
+            cpu_players.push(deal_hand(rules.hand_size, deck_ref, pile_ref, rng_ref)
+                                 .expect(DEAL_FITS));
         }
 
-        deal_parts(deck_ref, pile_ref, rng_ref)
+        deal_parts(deck_ref, pile_ref, rng_ref).expect(DEAL_FITS)
     };
 
     pile.push(pile_card);
 
+    let mode = match mode {
+        Mode::Match(ref lives) if lives.is_empty() => {
+            Mode::Match(vec![STARTING_LIVES; cpu_players_count + 1])
+        }
+        mode => mode,
+    };
+
+    let dealer = match (last_dealer, &mode) {
+        (Some(last_dealer), _) => turns::next_playing_seat(&mode, &last_dealer, cpu_players_count),
+        //the tutorial is written for you going first.
+        (None, &Mode::Tutorial(_, _)) => Cpu(cpu_players_count - 1),
+        (None, _) => {
+            match rng.gen_range(0, cpu_players_count + 1) {
+                0 => Player,
+                seat => Cpu(seat - 1),
+            }
+        }
+    };
+    let first = turns::next_playing_seat(&mode, &dealer, cpu_players_count);
+
     State {
         rng,
         title_screen,
+        mode,
         deck,
         pile,
         player,
         cpu_players,
-        turn: PlayerTurn(None),
+        dealer,
+        rules,
+        turn: turns::turn_for(&first),
         turn_count: 0,
+        round: 0,
         summary: String::new(),
+        cpu_last_actions: vec![String::new(); cpu_players_count],
+        animations: Vec::new(),
+        odds: None,
+        settings,
+        statistics,
+        dialog: None,
         ui_context: UIContext::new(),
     }
 }
 
-#[no_mangle]
 //returns true if quit requested
 pub fn update_and_render(platform: &Platform, state: &mut State, events: &mut Vec<Event>) -> bool {
-    if state.title_screen {
+    //everything else assumes it starts out on the bottom layer.
+    (platform.set_layer)(0);
+    theme::draw_table_background(platform, &theme::palette(state.settings.theme));
 
-        for event in events {
-            cross_mode_event_handling(platform, state, event);
-            match *event {
-                Event::Close |
-                Event::KeyPressed {
-                    key: KeyCode::Escape,
-                    ctrl: _,
-                    shift: _,
-                } => return true,
-                Event::KeyPressed {
-                    key: _,
-                    ctrl: _,
-                    shift: _,
-                } => state.title_screen = false,
-                _ => (),
-            }
-        }
-
-        (platform.print_xy)(5, 10, "Click to start.");
-
-        false
+    if state.title_screen {
+        title::update_and_render(platform, state, events)
     } else {
         game_update_and_render(platform, state, events)
     }
@@ -154,343 +277,833 @@ pub fn game_update_and_render(platform: &Platform,
                               state: &mut State,
                               events: &mut Vec<Event>)
                               -> bool {
-    let mut left_mouse_pressed = false;
-    let mut left_mouse_released = false;
+    let mut input = Input::default();
 
     for event in events {
         cross_mode_event_handling(platform, state, event);
+        input.record(event);
 
         match *event {
             Event::KeyPressed {
-                key: KeyCode::MouseLeft,
+                key: KeyCode::Escape,
                 ctrl: _,
                 shift: _,
-            } => {
-                left_mouse_pressed = true;
-            }
-            Event::KeyReleased {
-                key: KeyCode::MouseLeft,
+            } if state.dialog.is_some() => state.dialog = None,
+            Event::KeyPressed {
+                key: KeyCode::Escape,
                 ctrl: _,
                 shift: _,
             } => {
-                left_mouse_released = true;
+                //back to the main menu, where "Continue" picks up from here.
+                saved_game::save(state);
+                state.title_screen = true;
+                state.ui_context.menu_focus = 0;
+
+                return false;
             }
-            Event::Close |
             Event::KeyPressed {
-                key: KeyCode::Escape,
-                ctrl: _,
+                key: KeyCode::T,
+                ctrl: false,
                 shift: _,
-            } => return true,
+            } => {
+                state.settings.show_tracker = !state.settings.show_tracker;
+                settings::save(&state.settings);
+            }
+            Event::Close => {
+                saved_game::save(state);
+
+                return true;
+            }
             _ => (),
         }
     }
 
+    let layout = Layout::new((platform.size)());
+
+    if layout.too_small {
+        layout::draw_too_small_notice(platform, layout.size);
+
+        return false;
+    }
+
     state.ui_context.frame_init();
 
-    let redeal_spec = ButtonSpec {
-        x: 0,
-        y: 0,
-        w: 11,
-        h: 3,
-        text: "Redeal".to_string(),
-        id: 1,
+    let palette = theme::palette(state.settings.theme);
+
+    //let things finish moving before anything else on the table happens.
+    let (left_mouse_pressed, left_mouse_released) = if animation::update(state) {
+        (false, false)
+    } else {
+        (input.left_mouse_pressed, input.left_mouse_released)
+    };
+
+    //in a match you play the hand you're dealt, and in the tutorial the deal is
+    //the lesson.
+    if !in_match(state) && !tutorial::active(state) {
+        let redeal_position = layout.button(0);
+        let redeal_spec = ButtonSpec {
+            x: redeal_position.x,
+            y: redeal_position.y,
+            w: layout::BUTTON_WIDTH,
+            h: layout::BUTTON_HEIGHT,
+            text: "Redeal".to_string(),
+            id: state.ui_context.new_id(),
+        };
+
+        if do_button(platform,
+                     &mut state.ui_context,
+                     &palette,
+                     &redeal_spec,
+                     left_mouse_pressed,
+                     left_mouse_released) {
+            redeal(platform, state);
+        }
+    }
+
+    let settings_position = layout.button(2);
+    let settings_spec = ButtonSpec {
+        x: settings_position.x,
+        y: settings_position.y,
+        w: layout::BUTTON_WIDTH,
+        h: layout::BUTTON_HEIGHT,
+        text: "Settings".to_string(),
+        id: state.ui_context.new_id(),
     };
 
     if do_button(platform,
                  &mut state.ui_context,
-                 &redeal_spec,
+                 &palette,
+                 &settings_spec,
                  left_mouse_pressed,
                  left_mouse_released) {
-        *state = make_state((platform.size)(), false, state.rng);
+        open_dialog(state, Dialog::Settings);
     }
 
-    let size = (platform.size)();
-    let hand_height = size.height - HAND_HEIGHT_OFFSET;
-
     let mut should_draw_hand = true;
+    let was_resolved = resolved(&state.turn);
+
+    blitz_on_deal(state);
+    let seat_before = turns::seat_to_play(&state.turn);
 
     match state.turn.clone() {
         PlayerTurn(possible_knocker) => {
-            if let Some(knocker) = possible_knocker.clone() {
-                if knocker == Player {
-                    state.turn = Resolution(None);
-                }
-            } else {
+            if possible_knocker.is_none() && can_knock(state) {
+                let knock_position = layout.button(1);
                 let knock_spec = ButtonSpec {
-                    x: 0,
-                    y: 3,
-                    w: 11,
-                    h: 3,
+                    x: knock_position.x,
+                    y: knock_position.y,
+                    w: layout::BUTTON_WIDTH,
+                    h: layout::BUTTON_HEIGHT,
                     text: "Knock".to_string(),
-                    id: 4,
+                    id: state.ui_context.new_id(),
                 };
 
                 if do_button(platform,
                              &mut state.ui_context,
+                             &palette,
                              &knock_spec,
                              left_mouse_pressed,
-                             left_mouse_released) {
-                    state.turn = CpuTurn(Some(Player));
+                             left_mouse_released) &&
+                   tutorial::permit(state, Action::Knock) {
+                    if let Some(holder) = revealed_31(state) {
+                        state.turn = Resolution(Some(Winner(holder)));
+                    } else {
+                        end_player_turn(state, Some(Player));
+                    }
                 }
             }
 
             if state.deck.len() > 0 {
                 if do_card_back_button(platform,
                                        &mut state.ui_context,
-                                       DECK_X,
-                                       DECK_Y,
+                                       &palette,
+                                       layout.deck.x,
+                                       layout.deck.y,
+                                       layout.card,
                                        left_mouse_pressed,
-                                       left_mouse_released,
-                                       88) {
-                    let card = deal(state);
-                    state.turn = PlayerSelected(card, possible_knocker.clone());
+                                       left_mouse_released) &&
+                   tutorial::permit(state, Action::DrawDeck) {
+                    //the deck isn't empty, so this can't run out.
+                    if let Ok(card) = deal(state) {
+                        animation::animate(state,
+                                           Some(card.clone()),
+                                           Spot::Deck,
+                                           Spot::Selected,
+                                           0);
+                        state.turn = PlayerSelected(card, possible_knocker.clone());
+                    }
                 }
             }
 
-            let selected_top_card = if let Some(top_card) = state.pile.last() {
+            let selected_top_card = if let Some(top_card) = animation::displayed_pile_top(state) {
                 do_card_button(platform,
                                &mut state.ui_context,
-                               PILE_X,
-                               PILE_Y,
-                               top_card,
+                               &palette,
+                               layout.pile.x,
+                               layout.pile.y,
+                               layout.card,
+                               &top_card,
                                left_mouse_pressed,
-                               left_mouse_released,
-                               100)
+                               left_mouse_released)
             } else {
                 false
             };
 
-            if selected_top_card {
-                state.turn = PlayerSelected(state.pile.pop().unwrap(), possible_knocker.clone());
+            if selected_top_card && tutorial::permit(state, Action::TakePile) {
+                let card = state.pile.pop().unwrap();
+                animation::animate(state, Some(card.clone()), Spot::Pile, Spot::Selected, 0);
+                state.turn = PlayerSelected(card, possible_knocker.clone());
             }
         }
         PlayerSelected(selected_card, possible_knocker) => {
             let selection = select_returned_card(platform,
                                                  state,
+                                                 &palette,
                                                  &selected_card,
-                                                 hand_height,
+                                                 &layout,
                                                  left_mouse_pressed,
                                                  left_mouse_released);
             should_draw_hand = false;
 
+            let permitted = match selection {
+                Some(FromHand(index)) => {
+                    tutorial::permit(state, Action::DiscardFromHand(index))
+                }
+                Some(SelectedCard) => tutorial::permit(state, Action::DiscardSelected),
+                None => true,
+            };
+            let selection = if permitted { selection } else { None };
+
             match selection {
                 Some(FromHand(index)) => {
-                    state.pile.push(state.player.swap(index, selected_card));
-                    state.turn = if state.player.is_31() {
-                        Resolution(Some(Player))
+                    let returned_card = state.player.swap(index, selected_card.clone());
+                    let hand_spot = Spot::PlayerHand(index);
+
+                    animation::animate(state, Some(returned_card.clone()), hand_spot, Spot::Pile, 0);
+                    animation::animate(state, Some(selected_card), Spot::Selected, hand_spot, 0);
+
+                    state.pile.push(returned_card);
+
+                    if state.player.is_31(&state.rules) && !state.rules.hidden_31 {
+                        state.turn = Resolution(Some(Winner(Player)));
                     } else {
-                        CpuTurn(possible_knocker)
-                    };
+                        end_player_turn(state, possible_knocker);
+                    }
                 }
                 Some(SelectedCard) => {
+                    animation::animate(state,
+                                       Some(selected_card.clone()),
+                                       Spot::Selected,
+                                       Spot::Pile,
+                                       0);
                     state.pile.push(selected_card);
-                    state.turn = CpuTurn(possible_knocker);
+                    end_player_turn(state, possible_knocker);
                 }
                 None => {}
             }
 
-            if let Some(top_card) = state.pile.last() {
-                draw_card(platform, PILE_X, PILE_Y, top_card);
-            }
-
-            if state.deck.len() > 0 {
-                draw_card_back(platform, DECK_X, DECK_Y);
-            }
+            draw_table(platform, &palette, state, &layout);
 
             state.turn_count += 1;
         }
-        CpuTurn(possible_knocker) => {
-            let labeled = possible_knocker.clone().map(Knocker);
-
-            let turn_result = cpu_turns(state, labeled.clone());
-
-            state.turn = if possible_knocker.is_some() && labeled == turn_result {
-                Resolution(None)
-            } else {
-                CpuSummary(turn_result)
-            }
+        CpuTurn(_, _) if state.animations.len() > 0 => {
+            //wait for the previous cpu's moves to finish playing
+            draw_table(platform, &palette, state, &layout);
         }
-        CpuSummary(possible_knocker_or_winner) => {
-            (platform.print_xy)(12, 2, &state.summary);
-
-            let ok_spec = ButtonSpec {
-                x: 30,
-                y: 18,
-                w: 11,
-                h: 3,
-                text: "Okay".to_string(),
-                id: 4,
+        CpuTurn(possible_knocker_or_winner, cpu_index) => {
+            draw_table(platform, &palette, state, &layout);
+
+            let turn_result = take_cpu_turn(state, cpu_index, possible_knocker_or_winner.clone());
+
+            state.turn = match turn_result.clone() {
+                Some(Winner(_)) => CpuSummary(turn_result),
+                Some(Knocker(ref knocker)) => {
+                    match turns::after(state, &Cpu(cpu_index), Some(knocker)) {
+                        turns::Next::Seat(Cpu(i)) => CpuTurn(turn_result.clone(), i),
+                        turns::Next::Seat(Player) => CpuSummary(turn_result.clone()),
+                        turns::Next::Resolution => Resolution(Some(Knocker(knocker.clone()))),
+                    }
+                }
+                None => {
+                    match turns::after(state, &Cpu(cpu_index), None) {
+                        turns::Next::Seat(Cpu(i)) => CpuTurn(None, i),
+                        //the moves have already played out, so there's nothing
+                        //to summarize.
+                        _ => PlayerTurn(None),
+                    }
+                }
             };
+        }
+        CpuSummary(possible_knocker_or_winner) => {
+            draw_table(platform, &palette, state, &layout);
+
+            let summary = &state.summary;
+            let ui_context = &mut state.ui_context;
+
+            let pressed_ok = layers::with_layer(platform, Layer::Dialog, || {
+                let dialog = layout.dialog(text_box_size(summary, layout::BUTTON_HEIGHT + 1));
+
+                draw_text_box(platform, dialog, summary, palette.table, palette.text);
+
+                let ok_spec = ButtonSpec {
+                    x: dialog.top_left.x + (dialog.size.width - layout::BUTTON_WIDTH) / 2,
+                    y: dialog.top_left.y + dialog.size.height - layout::BUTTON_HEIGHT - 1,
+                    w: layout::BUTTON_WIDTH,
+                    h: layout::BUTTON_HEIGHT,
+                    text: "Okay".to_string(),
+                    id: ui_context.new_id(),
+                };
 
-            if do_button(platform,
-                         &mut state.ui_context,
-                         &ok_spec,
-                         left_mouse_pressed,
-                         left_mouse_released) {
+                do_button(platform,
+                          ui_context,
+                          &palette,
+                          &ok_spec,
+                          left_mouse_pressed,
+                          left_mouse_released)
+            });
+
+            if pressed_ok {
 
                 state.turn = match possible_knocker_or_winner {
-                    Some(Winner(winner)) => Resolution(Some(winner)),
-                    Some(Knocker(Player)) => Resolution(None),
+                    Some(Winner(winner)) => Resolution(Some(Winner(winner))),
                     Some(Knocker(p)) => PlayerTurn(Some(p)),
-                    _ => PlayerTurn(None),
-                }
+                    None => PlayerTurn(None),
+                };
             }
         }
-        Resolution(possible_winner) => {
-            let mut y = 2;
+        Resolution(ending) => {
+            let outcome = resolution::resolve(state, &ending);
+            let area = layout.results;
 
-            (platform.print_xy)(15,
-                                y,
-                                s!("You have {} ({})", state.player, state.player.score()));
+            let mut results = Vec::new();
+
+            if !outcome.winners.is_empty() {
+                results.push(format!("{} won!", listed(&outcome.winners)));
+            }
 
-            y += 2;
+            //everyone who lost the same amount goes on one line, so this stays
+            //short however many are playing.
+            let mut amounts: Vec<u32> = outcome.losers.iter().map(|&(_, lost)| lost).collect();
+            amounts.sort();
+            amounts.dedup();
+
+            for lost in amounts {
+                let losers: Vec<Participant> = outcome
+                    .losers
+                    .iter()
+                    .filter(|&&(_, l)| l == lost)
+                    .map(|&(ref p, _)| p.clone())
+                    .collect();
+
+                results.push(match (state.mode.lives(&losers[0]), lost) {
+                                 (Some(_), 1) => format!("{} lost a life.", listed(&losers)),
+                                 (Some(_), lost) => {
+                                     format!("{} lost {} lives.", listed(&losers), lost)
+                                 }
+                                 (None, _) => format!("{} came last.", listed(&losers)),
+                             });
+            }
 
-            for (i, cpu_hand) in state.cpu_players.iter().enumerate() {
-                (platform.print_xy)(15,
-                                    y,
-                                    s!("{} has {}, ({})", Cpu(i), cpu_hand, cpu_hand.score()));
+            let finished = match state.mode {
+                Mode::Match(_) if match_over(&state.mode) => {
+                    Some(if state.mode.is_out(&Player) {
+                             "You lost the match."
+                         } else {
+                             "You won the match!"
+                         })
+                }
+                Mode::Tutorial(_, _) => Some(tutorial::FINISHED),
+                _ => None,
+            };
 
-                y += 2;
+            if let Some(result) = finished {
+                results.push(String::new());
+                results.push(result.to_string());
             }
 
-            let winners = possible_winner
-                .map(|v| vec![v])
-                .unwrap_or_else(|| {
-                    let mut who_scored = HashMap::new();
-                    {
-                        let player_score = state.player.score();
-                        let e = who_scored.entry(player_score).or_insert(Vec::new());
-                        e.push(Player);
+            let scores: Vec<String> = outcome
+                .scores
+                .iter()
+                .map(|&(ref participant, _)| {
+                    let hand = resolution::hand_of(state, participant);
+                    let lives = lives_text(&state.mode, participant);
+                    //how the score was arrived at, rather than just the number
+                    let why = hand.breakdown(&state.rules).explanation();
+
+                    match *participant {
+                        Player => format!("You have {} ({}){}", hand, why, lives),
+                        Cpu(_) => format!("{} has {}, ({}){}", participant, hand, why, lives),
                     }
+                })
+                .collect();
+
+            //the results sit just above the hand, and the scores get whatever
+            //room is left over above them, less a blank line.
+            let width = area.size.width as usize;
+            let results_y = area.top_left.y + area.size.height - results.len() as i32;
+            let room = std::cmp::max(results_y - 1 - area.top_left.y, 0) as usize;
+            let spacing = if scores.len() * 2 <= room { 2 } else { 1 };
+            let shown = if scores.len() > room {
+                room.saturating_sub(1)
+            } else {
+                scores.len()
+            };
 
-                    for i in 0..state.cpu_players.len() {
-                        let cpu_score = state.cpu_players[i].score();
-                        let e = who_scored.entry(cpu_score).or_insert(Vec::new());
-                        e.push(Cpu(i));
-                    }
+            let mut y = area.top_left.y;
+            for line in scores.iter().take(shown) {
+                print_truncated(platform, area.top_left.x, y, width, line);
+                y += spacing;
+            }
+
+            if shown < scores.len() {
+                print_truncated(platform,
+                                area.top_left.x,
+                                y,
+                                width,
+                                s!("…and {} more.", scores.len() - shown));
+            }
+
+            for (i, line) in results.iter().enumerate() {
+                print_truncated(platform, area.top_left.x, results_y + i as i32, width, line);
+            }
 
-                    let winner_key = {
-                        who_scored.keys().max().unwrap().clone()
-                    };
+            if in_match(state) || finished.is_some() {
+                let over = finished.is_some();
+
+                let next_position = layout.button(0);
+                let next_spec = ButtonSpec {
+                    x: next_position.x,
+                    y: next_position.y,
+                    w: layout::BUTTON_WIDTH,
+                    h: layout::BUTTON_HEIGHT,
+                    text: if over { "Main menu" } else { "Next deal" }.to_string(),
+                    id: state.ui_context.new_id(),
+                };
+
+                if do_button(platform,
+                             &mut state.ui_context,
+                             &palette,
+                             &next_spec,
+                             left_mouse_pressed,
+                             left_mouse_released) {
+                    if over {
+                        state.title_screen = true;
+                        state.ui_context.menu_focus = 0;
 
-                    who_scored.remove(&winner_key).unwrap()
-                });
+                        return false;
+                    }
 
-            let mut winner_y = 20 - (winners.len() - 1) as i32;
-            for winner in winners.iter() {
-                (platform.print_xy)(10, winner_y, s!("{} won!", winner));
-                winner_y += 1;
+                    redeal(platform, state);
+                }
             }
+        }
+    }
+
+    //a fresh deal starts on round 0, even though it's also the first seat's go.
+    let seat_after = turns::seat_to_play(&state.turn);
+    if seat_after != seat_before && seat_after == Some(turns::first_to_play(state)) &&
+       state.turn_count > 0 {
+        state.round += 1;
+    }
 
+    if !was_resolved {
+        if let Resolution(ending) = state.turn.clone() {
+            finish_deal(state, ending);
         }
     }
 
+    draw_opponents(platform, &palette, state, &layout);
+    draw_seat_tooltip(platform, &palette, state, &layout);
+    tutorial::draw_prompt(platform, &palette, state, &layout);
+    score_panel::update(state);
+    score_panel::draw(platform, state, &layout);
+    tracker::draw(platform, &palette, state, &layout);
+
+    draw_player_status(platform, state, &layout);
+
     if should_draw_hand {
-        match state.player {
-            Hand(ref c1, ref c2, ref c3) => {
+        layers::with_layer(platform, Layer::Cards, || {
+            for (i, card) in state.player.cards().iter().enumerate() {
+                if animation::is_arriving(state, Spot::PlayerHand(i)) {
+                    continue;
+                }
 
-                let mut x = CARD_OFFSET;
+                let position = layout.hand_card(i as i32);
 
-                draw_card(platform, x, hand_height, c1);
-                x += CARD_OFFSET_DELTA;
+                draw_card_sized(platform,
+                                &palette,
+                                position.x,
+                                position.y,
+                                layout.card.width,
+                                layout.card.height,
+                                card);
+            }
+        });
+    }
 
-                draw_card(platform, x, hand_height, c2);
-                x += CARD_OFFSET_DELTA;
+    animation::draw(platform, &palette, state, &layout);
 
-                draw_card(platform, x, hand_height, c3);
+    do_dialog(platform, state, &palette, &layout, &input);
 
-            }
+    false
+}
+
+//a new deal, in the same mode, (and match,) as the last one.
+fn redeal(platform: &Platform, state: &mut State) {
+    *state = make_state((platform.size)(),
+                        false,
+                        state.mode.clone(),
+                        state.settings.clone(),
+                        state.statistics.clone(),
+                        Some(state.dealer.clone()),
+                        state.rng);
+}
+
+fn in_match(state: &State) -> bool {
+    match state.mode {
+        Mode::Match(_) => true,
+        _ => false,
+    }
+}
+
+fn resolved(turn: &Turn) -> bool {
+    match *turn {
+        Resolution(_) => true,
+        _ => false,
+    }
+}
+
+//Called once, on the frame the deal is over.
+fn finish_deal(state: &mut State, ending: Option<KnockerOrWinner>) {
+    let outcome = resolution::resolve(state, &ending);
+    let won = outcome.winners.contains(&Player);
+
+    let counts = match state.mode {
+        Mode::Practice | Mode::Tutorial(_, _) => false,
+        _ => true,
+    };
+
+    if counts {
+        statistics::record_deal(&mut state.statistics, won, outcome.thirty_one == Some(Player));
+    }
+
+    if !in_match(state) {
+        return;
+    }
+
+    if let Mode::Match(ref mut lives) = state.mode {
+        for (loser, lost) in outcome.losers {
+            let index = match loser {
+                Player => 0,
+                Cpu(i) => i + 1,
+            };
+
+            lives[index] = lives[index].saturating_sub(lost);
         }
     }
 
-    false
+    if match_over(&state.mode) {
+        let won = !state.mode.is_out(&Player);
+
+        statistics::record_match(&mut state.statistics, won);
+    }
 }
 
-fn cpu_turns(state: &mut State,
-             possible_knocker_or_winner: Option<KnockerOrWinner>)
-             -> Option<KnockerOrWinner> {
-    state.summary.clear();
+//A match ends when you're out, or everyone else is.
+fn match_over(mode: &Mode) -> bool {
+    match *mode {
+        Mode::Match(ref lives) if !lives.is_empty() => {
+            lives[0] == 0 || lives[1..].iter().all(|&l| l == 0)
+        }
+        _ => false,
+    }
+}
+
+fn lives_text(mode: &Mode, participant: &Participant) -> String {
+    match mode.lives(participant) {
+        Some(0) => " OUT".to_string(),
+        Some(1) => " 1 life".to_string(),
+        Some(lives) => format!(" {} lives", lives),
+        None => String::new(),
+    }
+}
 
-    let mut possible_result = possible_knocker_or_winner.clone();
+//"You", "You and Cpu 1" or "Cpu 0, Cpu 1 and Cpu 2".
+fn listed(participants: &[Participant]) -> String {
+    let names: Vec<String> = participants.iter().map(|p| p.to_string()).collect();
 
-    for i in 0..state.cpu_players.len() {
-        possible_result = take_cpu_turn(state, i, possible_result);
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => names.join(""),
+    }
+}
+
+fn open_dialog(state: &mut State, dialog: Dialog) {
+    state.dialog = Some(dialog);
+    state.ui_context.menu_focus = 0;
+}
 
-        match possible_result {
-            Some(Knocker(ref knocker)) => {
-                if knocker == &Cpu(i) {
-                    break;
+//Whichever dialog is open goes over everything else.
+fn do_dialog(platform: &Platform,
+             state: &mut State,
+             palette: &Palette,
+             layout: &Layout,
+             input: &Input) {
+    let context = &mut state.ui_context;
+
+    let closed = match state.dialog {
+        Some(Dialog::Settings) => {
+            let outcome = settings::do_menu(platform,
+                                            context,
+                                            palette,
+                                            layout,
+                                            input,
+                                            &mut state.settings);
+
+            match outcome {
+                settings::Outcome::Open => false,
+                settings::Outcome::Done => true,
+                settings::Outcome::HouseRules => {
+                    open_dialog(state, Dialog::HouseRules);
+                    false
+                }
+                settings::Outcome::CustomCardPoints => {
+                    open_dialog(state, Dialog::CardPoints);
+                    false
                 }
             }
-            Some(_) => {
-                break;
+        }
+        Some(Dialog::HouseRules) => {
+            let outcome = settings::do_house_rules_menu(platform,
+                                                        context,
+                                                        palette,
+                                                        layout,
+                                                        input,
+                                                        &mut state.settings);
+
+            match outcome {
+                settings::Outcome::Open => false,
+                settings::Outcome::Done => {
+                    open_dialog(state, Dialog::Settings);
+                    false
+                }
+                settings::Outcome::HouseRules => false,
+                settings::Outcome::CustomCardPoints => {
+                    open_dialog(state, Dialog::CardPoints);
+                    false
+                }
+            }
+        }
+        Some(Dialog::CardPoints) => {
+            let done = settings::do_card_points_menu(platform,
+                                                     context,
+                                                     palette,
+                                                     layout,
+                                                     input,
+                                                     &mut state.settings);
+
+            if done {
+                open_dialog(state, Dialog::HouseRules);
             }
-            None => {}
+
+            false
+        }
+        Some(Dialog::Statistics) => {
+            statistics::do_dialog(platform, context, palette, layout, input, &state.statistics)
+        }
+        Some(Dialog::Rules(ref mut page)) => {
+            //on the main menu, it's the rules the next game will use.
+            let rules = if state.title_screen {
+                &state.settings.rules
+            } else {
+                &state.rules
+            };
+
+            help::do_rules_dialog(platform, context, palette, layout, input, rules, page)
+        }
+        None => false,
+    };
+
+    if closed {
+        state.dialog = None;
+    }
+}
+
+//With `Ruleset::blitz_on_deal`, a 31 as dealt wins before anyone has moved.
+fn blitz_on_deal(state: &mut State) {
+    if state.rules.blitz_on_deal && state.turn_count == 0 && !resolved(&state.turn) {
+        if let Some(holder) = resolution::thirty_one(state) {
+            state.turn = Resolution(Some(Winner(holder)));
         }
     }
+}
 
-    possible_result
+//With `Ruleset::hidden_31`, a knock brings out any 31 that's been kept quiet.
+fn revealed_31(state: &State) -> Option<Participant> {
+    if state.rules.hidden_31 {
+        resolution::thirty_one(state)
+    } else {
+        None
+    }
+}
+
+//Hands the turn on from you, to whoever `turns::after` says is next.
+fn end_player_turn(state: &mut State, possible_knocker: Option<Participant>) {
+    //the summary is of what happened since your last go.
+    state.summary.clear();
+
+    state.turn = match turns::after(state, &Player, possible_knocker.as_ref()) {
+        turns::Next::Seat(Cpu(i)) => CpuTurn(possible_knocker.map(Knocker), i),
+        turns::Next::Seat(Player) => PlayerTurn(possible_knocker),
+        turns::Next::Resolution => Resolution(possible_knocker.map(Knocker)),
+    };
+}
+
+//the tutorial is written for one difficulty, like it is for the standard rules.
+fn cpu_difficulty(state: &State) -> Difficulty {
+    if tutorial::active(state) {
+        tutorial::DIFFICULTY
+    } else {
+        state.settings.difficulty
+    }
+}
+
+fn can_knock(state: &State) -> bool {
+    state.rules.knock_on_first_round || state.round > 0
+}
+
+//the pile and deck when they can't be clicked on
+fn draw_table(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    layers::with_layer(platform, Layer::Cards, || {
+        if let Some(top_card) = animation::displayed_pile_top(state) {
+            draw_card_sized(platform,
+                            palette,
+                            layout.pile.x,
+                            layout.pile.y,
+                            layout.card.width,
+                            layout.card.height,
+                            &top_card);
+        }
+
+        if state.deck.len() > 0 {
+            draw_card_back_sized(platform,
+                                 palette,
+                                 layout.deck.x,
+                                 layout.deck.y,
+                                 layout.card.width,
+                                 layout.card.height);
+        }
+    });
 }
 
 fn take_cpu_turn(state: &mut State,
                  cpu_index: usize,
                  possible_knocker_or_winner: Option<KnockerOrWinner>)
                  -> Option<KnockerOrWinner> {
+    if state.mode.is_out(&Cpu(cpu_index)) {
+        return possible_knocker_or_winner;
+    }
+
     state.turn_count += 1;
 
-    if let Some(Knocker(ref p)) = possible_knocker_or_winner {
-        if p == &Cpu(cpu_index) {
-            return Some(Knocker(p.clone()));
-        }
-    }
+    let can_knock = can_knock(state);
+    let difficulty = cpu_difficulty(state);
+    let hidden_31 = state.rules.hidden_31;
+    let rules = state.rules.clone();
 
     if let Some(cpu_hand) = state.cpu_players.get_mut(cpu_index) {
         if let Some(Knocker(_)) = possible_knocker_or_winner {
             //don't allow knocking
-        } else {
-            if cpu_hand.score() >= Simple(25) ||
-               (state.turn_count <= 5 && cpu_hand.score() >= Simple(19)) {
+        } else if can_knock {
+            if cpu_should_knock(difficulty, cpu_hand.score(&rules), state.turn_count, &rules) {
                 state.summary += s!("Cpu {} knocked!\n", cpu_index);
-                return Some(Knocker(Cpu(cpu_index)));
+                state.cpu_last_actions[cpu_index] = "knocked!".to_string();
+
+                return Some(match revealed_31(state) {
+                                Some(holder) => {
+                                    state.summary += s!("{} had 31!\n", holder);
+                                    Winner(holder)
+                                }
+                                None => Knocker(Cpu(cpu_index)),
+                            });
             }
         }
 
 
-        let pile_card_is_worth_taking = state
-            .pile
-            .last()
-            .map(|card| match cpu_select_returned_card(cpu_hand, card) {
-                     SelectedCard => false,
-                     _ => true,
-                 })
-            .unwrap_or(false);
+        let pile_card_is_worth_taking = if difficulty == Difficulty::Easy {
+            //on easy, the cpus don't pay attention to the pile.
+            false
+        } else {
+            state
+                .pile
+                .last()
+                .map(|card| match cpu_select_returned_card(cpu_hand, card, &rules) {
+                         SelectedCard => false,
+                         _ => true,
+                     })
+                .unwrap_or(false)
+        };
 
         let card = if pile_card_is_worth_taking {
             let card = state.pile.pop().unwrap();
 
+            animation::animate_parts(&mut state.animations,
+                                     state.ui_context.frame,
+                                     Some(card.clone()),
+                                     Spot::Pile,
+                                     Spot::Seat(cpu_index),
+                                     animation::THINK_FRAMES);
+
             state.summary += s!("Cpu {} picked up the {} off the pile\n", cpu_index, card);
+            state.cpu_last_actions[cpu_index] = format!("took {}", card);
 
             card
         } else {
-            let card = deal_parts(&mut state.deck, &mut state.pile, &mut state.rng);
+            let card = match deal_parts(&mut state.deck, &mut state.pile, &mut state.rng) {
+                Ok(card) => card,
+                Err(OutOfCards) => {
+                    //the deal can't go on, so they knock to bring it to an end
+                    //if no one else has.
+                    state.summary += s!("Cpu {} had nothing to draw.\n", cpu_index);
+                    state.cpu_last_actions[cpu_index] = "had nothing to draw".to_string();
+
+                    return possible_knocker_or_winner.or(Some(Knocker(Cpu(cpu_index))));
+                }
+            };
+
+            animation::animate_parts(&mut state.animations,
+                                     state.ui_context.frame,
+                                     None,
+                                     Spot::Deck,
+                                     Spot::Seat(cpu_index),
+                                     animation::THINK_FRAMES);
 
             state.summary += s!("Cpu {} drew a card ", cpu_index);
+            state.cpu_last_actions[cpu_index] = "drew a card".to_string();
 
             card
         };
 
-        let choice = cpu_select_returned_card(&cpu_hand, &card);
+        let choice = cpu_select_returned_card(&cpu_hand, &card, &rules);
 
         let returned_card = match choice {
             FromHand(card_index) => cpu_hand.swap(card_index, card),
             SelectedCard => card,
         };
 
-        state.summary += s!("and put a {} back on the pile.\n\n", returned_card);
+        state.summary += s!("and put a {} back on the pile.\n", returned_card);
+        state.cpu_last_actions[cpu_index] += s!("\nput back {}", returned_card);
+
+        animation::animate_parts(&mut state.animations,
+                                 state.ui_context.frame,
+                                 Some(returned_card.clone()),
+                                 Spot::Seat(cpu_index),
+                                 Spot::Pile,
+                                 0);
 
         state.pile.push(returned_card);
 
-        if cpu_hand.is_31() {
+        if cpu_hand.is_31(&rules) && !hidden_31 {
             return Some(Winner(Cpu(cpu_index)));
         }
     }
@@ -498,108 +1111,283 @@ fn take_cpu_turn(state: &mut State,
     possible_knocker_or_winner
 }
 
-fn cpu_select_returned_card(hand: &HandEnum, card: &Card) -> ReturnSelection {
-    match hand {
-        &Hand(ref c1, ref c2, ref c3) => {
-
-            let keep = score_cards(c1, c2, c3);
-            let first = score_cards(card, c2, c3);
-            let second = score_cards(c1, card, c3);
-            let third = score_cards(c1, c2, card);
-
-            if let Some(max) = [&keep, &first, &second, &third]
-                   .iter()
-                   .max()
-                   .map(|&r| r.clone()) {
-                if max == keep {
-                    SelectedCard
-                } else if max == first {
-                    FromHand(FirstCard)
-                } else if max == second {
-                    FromHand(SecondCard)
-                } else {
-                    FromHand(ThirdCard)
+fn cpu_should_knock(difficulty: Difficulty,
+                    score: Score,
+                    turn_count: u32,
+                    rules: &Ruleset)
+                    -> bool {
+    let early = turn_count <= 5;
+    //the thresholds are for hands out of 31, so they scale with the best score.
+    let at_least = |points: u32| {
+        score >= Simple((points * rules.best_score() as u32 / 31) as u8)
+    };
+
+    match difficulty {
+        Difficulty::Easy => at_least(22),
+        Difficulty::Normal => at_least(25) || (early && at_least(19)),
+        Difficulty::Hard => at_least(27) || (early && at_least(23)),
+    }
+}
+
+//Whichever choice leaves the best hand, keeping the hand as it is on a tie.
+fn cpu_select_returned_card(hand: &HandEnum, card: &Card, rules: &Ruleset) -> ReturnSelection {
+    let mut choice = SelectedCard;
+    let mut best = hand.score(rules);
+
+    for i in 0..hand.cards().len() {
+        let score = hand.with_swapped(i, card).score(rules);
+
+        if score > best {
+            choice = FromHand(i);
+            best = score;
+        }
+    }
+
+    choice
+}
+
+fn draw_opponents(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    let count = state.cpu_players.len() as i32;
+    let knocker = knocker(&state.turn);
+
+    for i in 0..state.cpu_players.len() {
+        let seat = layout.seat(i as i32, count);
+        let width = std::cmp::max(seat.size.width - 1, 0) as usize;
+        let mut y = seat.top_left.y;
+
+        let name = if taking_turn(state) == Some(Cpu(i)) {
+            format!("►{}◄", Cpu(i))
+        } else {
+            Cpu(i).to_string()
+        };
+        let badge = if knocker == Some(Cpu(i)) {
+            " KNOCKED"
+        } else if state.dealer == Cpu(i) {
+            " (dealer)"
+        } else {
+            ""
+        };
+
+        print_truncated(platform,
+                        seat.top_left.x,
+                        y,
+                        width,
+                        &format!("{}{}{}", name, badge, lives_text(&state.mode, &Cpu(i))));
+        y += 1;
+
+        if state.mode.is_out(&Cpu(i)) {
+            continue;
+        }
+
+        //in practice you get to see what the cpus are up to.
+        let face_up = state.mode == Mode::Practice;
+
+        if !layout::seat_shows_cards(&seat) {
+            let cards: Vec<String> = state.cpu_players[i]
+                .cards()
+                .iter()
+                .map(|card| if face_up {
+                         format!("{}{}", card.value, card_face::suit_text(card))
+                     } else {
+                         "▒▒".to_string()
+                     })
+                .collect();
+
+            layers::with_layer(platform, Layer::Cards, || {
+                print_truncated(platform, seat.top_left.x, y, width, &cards.join(" "))
+            });
+            y += 1;
+        } else {
+            layers::with_layer(platform, Layer::Cards, || {
+                for (j, card) in state.cpu_players[i].cards().iter().enumerate() {
+                    let x = seat.top_left.x + j as i32 * SEAT_CARD_OFFSET;
+
+                    if face_up {
+                        draw_card_sized(platform,
+                                        palette,
+                                        x,
+                                        y,
+                                        SEAT_CARD_WIDTH,
+                                        SEAT_CARD_HEIGHT,
+                                        card);
+                    } else {
+                        draw_card_back_sized(platform,
+                                             palette,
+                                             x,
+                                             y,
+                                             SEAT_CARD_WIDTH,
+                                             SEAT_CARD_HEIGHT);
+                    }
                 }
-            } else {
-                FromHand(FirstCard)
+            });
+            y += SEAT_CARD_HEIGHT;
+        }
+
+        //with a lot of seats, only as much as fits before the next row of them.
+        let bottom = seat.top_left.y + seat.size.height;
+
+        for line in state.cpu_last_actions[i].lines() {
+            if y >= bottom {
+                break;
             }
+
+            print_truncated(platform, seat.top_left.x, y, width, line);
+            y += 1;
         }
     }
+}
+
+//Your lives and whether you're dealing, just above the hand where the buttons
+//leave some room.
+fn draw_player_status(platform: &Platform, state: &State, layout: &Layout) {
+    let mut lines = Vec::new();
+
+    if let Some(_) = state.mode.lives(&Player) {
+        lines.push(format!("You:{}", lives_text(&state.mode, &Player)));
+    }
+    if state.dealer == Player {
+        lines.push("You deal".to_string());
+    }
+
+    let top = layout.hand.y - lines.len() as i32;
+
+    for (i, line) in lines.iter().enumerate() {
+        print_truncated(platform, 0, top + i as i32, layout::BUTTON_WIDTH as usize, line);
+    }
+}
+
+//The captions under each seat get cut off, so hovering over a cpu's cards shows
+//all of it.
+fn draw_seat_tooltip(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    let mouse = (platform.mouse_position)();
+
+    if !layers::hit_test(platform, Layer::Cards, mouse) {
+        return;
+    }
+
+    let count = state.cpu_players.len();
+    let hovered = (0..count).find(|&i| {
+                                      let seat = layout.seat(i as i32, count as i32);
+
+                                      inside_rect(mouse,
+                                                  seat.top_left.x,
+                                                  seat.top_left.y,
+                                                  seat.size.width,
+                                                  seat.size.height)
+                                  });
+
+    if let Some(i) = hovered {
+        let text = format!("{}\n{}", Cpu(i), state.cpu_last_actions[i]);
+        let size = text_box_size(&text, 0);
+
+        //keep it on screen, even near the right edge.
+        let x = std::cmp::min(mouse.x + 1, layout.size.width - size.width);
+        let rect = Rect::from_values(std::cmp::max(x, 0), mouse.y + 1, size.width, size.height);
+
+        layers::with_layer(platform, Layer::Tooltip, || {
+            draw_text_box(platform, rect, &text, palette.card_face, palette.card_edge)
+        });
+    }
+}
 
+//How big a box needs to be to hold `text`, with `footer_height` rows left
+//over at the bottom for buttons.
+fn text_box_size(text: &str, footer_height: i32) -> Size {
+    let width = text.lines().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+    let height = text.lines().count() as i32;
+
+    Size::new(std::cmp::max(width, layout::BUTTON_WIDTH) + 4,
+              height + 2 + footer_height)
+}
+
+//An opaque box on the current layer, with `text` in it.
+fn draw_text_box(platform: &Platform,
+                 rect: Rect,
+                 text: &str,
+                 background: Color,
+                 foreground: Color) {
+    let (x, y) = (rect.top_left.x, rect.top_left.y);
+
+    layers::fill(platform, rect, background);
+
+    theme::with_colors(platform, foreground, background, || {
+        draw_rect(platform, x, y, rect.size.width, rect.size.height);
+
+        for (i, line) in text.lines().enumerate() {
+            (platform.print_xy)(x + 2, y + 1 + i as i32, line);
+        }
+    });
+}
+
+fn knocker(turn: &Turn) -> Option<Participant> {
+    match *turn {
+        PlayerTurn(ref possible_knocker) |
+        PlayerSelected(_, ref possible_knocker) => possible_knocker.clone(),
+        CpuTurn(Some(Knocker(ref knocker)), _) |
+        CpuSummary(Some(Knocker(ref knocker))) => Some(knocker.clone()),
+        _ => None,
+    }
+}
+
+fn taking_turn(state: &State) -> Option<Participant> {
+    if let Some(i) = animation::moving_seat(state) {
+        return Some(Cpu(i));
+    }
+
+    match state.turn {
+        PlayerTurn(_) | PlayerSelected(_, _) => Some(Player),
+        CpuTurn(_, i) => Some(Cpu(i)),
+        _ => None,
+    }
 }
 
 enum ReturnSelection {
-    FromHand(HandCard),
+    //the index of the card in your hand
+    FromHand(usize),
     SelectedCard,
 }
 use ReturnSelection::*;
 
 fn select_returned_card(platform: &Platform,
                         state: &mut State,
+                        palette: &Palette,
                         selected_card: &Card,
-                        hand_height: i32,
+                        layout: &Layout,
                         left_mouse_pressed: bool,
                         left_mouse_released: bool)
                         -> Option<ReturnSelection> {
 
-    let mut id = 110;
-    match state.player {
-        Hand(ref c1, ref c2, ref c3) => {
-
-            let mut x = CARD_OFFSET;
-
-            if do_raised_card_button(platform,
-                                     &mut state.ui_context,
-                                     x,
-                                     hand_height,
-                                     c1,
-                                     left_mouse_pressed,
-                                     left_mouse_released,
-                                     id) {
-                return Some(FromHand(FirstCard));
-            }
-
-            x += CARD_OFFSET_DELTA;
-            id += 1;
-
-            if do_raised_card_button(platform,
-                                     &mut state.ui_context,
-                                     x,
-                                     hand_height,
-                                     c2,
-                                     left_mouse_pressed,
-                                     left_mouse_released,
-                                     id) {
-                return Some(FromHand(SecondCard));
-            }
-
-
-            x += CARD_OFFSET_DELTA;
-            id += 1;
-
-            if do_raised_card_button(platform,
-                                     &mut state.ui_context,
-                                     x,
-                                     hand_height,
-                                     c3,
-                                     left_mouse_pressed,
-                                     left_mouse_released,
-                                     id) {
-                return Some(FromHand(ThirdCard));
-            }
+    for (i, card) in state.player.cards().iter().enumerate() {
+        let position = layout.hand_card(i as i32);
+        let score = state.player.with_swapped(i, selected_card).score(&state.rules);
+
+        if do_raised_card_button(platform,
+                                 &mut state.ui_context,
+                                 palette,
+                                 position.x,
+                                 position.y,
+                                 layout.card,
+                                 card,
+                                 &discard_caption(score),
+                                 left_mouse_pressed,
+                                 left_mouse_released) {
+            return Some(FromHand(i));
         }
     }
 
-    id += 1;
+    if animation::is_arriving(state, Spot::Selected) {
+        return None;
+    }
 
     if do_card_button(platform,
                       &mut state.ui_context,
-                      50,
-                      hand_height,
+                      palette,
+                      layout.selected.x,
+                      layout.selected.y,
+                      layout.card,
                       selected_card,
                       left_mouse_pressed,
-                      left_mouse_released,
-                      id) {
+                      left_mouse_released) {
         Some(SelectedCard)
     } else {
         None
@@ -607,94 +1395,120 @@ fn select_returned_card(platform: &Platform,
 }
 
 
-const PILE_X: i32 = 40;
-const DECK_X: i32 = PILE_X + CARD_WIDTH + 4;
-
-const PILE_Y: i32 = 10;
-const DECK_Y: i32 = PILE_Y;
-
-const CARD_OFFSET: i32 = 5;
-const CARD_OFFSET_DELTA: i32 = 6;
-
-const HAND_HEIGHT_OFFSET: i32 = 8;
-
-const CARD_WIDTH: i32 = 16;
-const CARD_HEIGHT: i32 = 12;
-
-fn draw_card(platform: &Platform, x: i32, y: i32, card: &Card) {
-    draw_rect(platform, x, y, CARD_WIDTH, CARD_HEIGHT);
-
-    draw_card_value(platform, x + 1, y + 1, card);
+fn draw_card_sized(platform: &Platform,
+                   palette: &Palette,
+                   x: i32,
+                   y: i32,
+                   w: i32,
+                   h: i32,
+                   card: &Card) {
+    layers::fill(platform, Rect::from_values(x, y, w, h), palette.card_face);
+    theme::with_colors(platform,
+                       palette.card_edge,
+                       palette.card_face,
+                       || draw_rect(platform, x, y, w, h));
+
+    card_face::draw(platform, palette, x, y, w, h, card);
 }
-fn draw_card_back(platform: &Platform, x: i32, y: i32) {
-    draw_rect(platform, x, y, CARD_WIDTH, CARD_HEIGHT);
-
-    draw_card_back_design(platform, x, y);
+fn draw_card_back_sized(platform: &Platform, palette: &Palette, x: i32, y: i32, w: i32, h: i32) {
+    layers::fill(platform, Rect::from_values(x, y, w, h), palette.card_face);
+    theme::with_colors(platform,
+                       palette.card_edge,
+                       palette.card_face,
+                       || draw_rect(platform, x, y, w, h));
+
+    draw_card_back_design_sized(platform, palette, x, y, w, h);
 }
 
-fn draw_card_value(platform: &Platform, x: i32, y: i32, card: &Card) {
-    (platform.print_xy)(x, y, &card.value.to_string());
-    (platform.print_xy)(x, y + 1, &card.suit.to_string());
-}
-fn draw_card_back_design(platform: &Platform, x: i32, y: i32) {
-    draw_rect(platform, x + 2, y + 1, CARD_WIDTH - 4, CARD_HEIGHT - 2);
+fn draw_card_back_design_sized(platform: &Platform,
+                               palette: &Palette,
+                               x: i32,
+                               y: i32,
+                               w: i32,
+                               h: i32) {
+    theme::with_colors(platform,
+                       palette.card_back,
+                       palette.card_face,
+                       || draw_rect(platform, x + 2, y + 1, w - 4, h - 2));
 }
 
-const CARD_RAISE_OFFSET: i32 = CARD_HEIGHT / 2;
+//the card backs shown in each opponent's seat
+const SEAT_CARD_WIDTH: i32 = 6;
+const SEAT_CARD_HEIGHT: i32 = 4;
+const SEAT_CARD_OFFSET: i32 = 3;
+
+const CARD_RAISE_FRAMES: u32 = 6;
+
+//what discarding a card would leave you with.
+fn discard_caption(score: Score) -> String {
+    format!("→ {}", score_panel::points(&score))
+}
 
-//TODO animation. Maybe put a counter on the UIContext?
+//`caption` is shown over the card while it's raised.
 fn do_raised_card_button(platform: &Platform,
                          context: &mut UIContext,
+                         palette: &Palette,
                          x: i32,
                          y: i32,
+                         size: Size,
                          card: &Card,
+                         caption: &str,
                          left_mouse_pressed: bool,
-                         left_mouse_released: bool,
-                         id: UiId)
+                         left_mouse_released: bool)
                          -> bool {
-    let mut result = false;
+    let id = context.new_id();
 
     let mouse_pos = (platform.mouse_position)();
     let active = context.active == id;
     let hot = context.hot == id;
-    let raised_y = y - CARD_RAISE_OFFSET;
-    let inside = inside_rect(mouse_pos, x, y, CARD_WIDTH, CARD_HEIGHT) ||
-                 (hot && inside_rect(mouse_pos, x, raised_y, CARD_WIDTH, CARD_HEIGHT));
-
-    if active {
-        if left_mouse_released {
-            result = hot && inside;
+    let (w, h) = (size.width, size.height);
+    let raise_offset = h / 2;
+    let raised_y = y - raise_offset;
+    let inside = inside_rect(mouse_pos, x, y, w, h) ||
+                 (hot && inside_rect(mouse_pos, x, raised_y, w, h));
 
-            context.set_not_active();
-        }
-    } else if hot {
-        if left_mouse_pressed {
-            context.set_active(id);
-        }
-    }
+    //the card slides up over a few frames after it becomes hot.
+    let raise_frames = std::cmp::min(context.frame.wrapping_sub(context.hot_since),
+                                      CARD_RAISE_FRAMES);
+    let drawn_y = y - raise_offset * raise_frames as i32 / CARD_RAISE_FRAMES as i32;
 
-    if inside {
-        context.set_next_hot(id);
-    }
+    let result = ui::clickable(context, id, inside, left_mouse_pressed, left_mouse_released);
 
-    if active && (platform.key_pressed)(KeyCode::MouseLeft) {
-        draw_rect_with(platform,
-                       x,
-                       raised_y,
-                       CARD_WIDTH,
-                       CARD_HEIGHT,
-                       ["╔", "═", "╕", "║", "│", "╙", "─", "┘"]);
-        draw_card_value(platform, x + 1, raised_y + 1, card);
+    let raised_edges = if active && (platform.key_pressed)(KeyCode::MouseLeft) {
+        Some(["╔", "═", "╕", "║", "│", "╙", "─", "┘"])
     } else if context.hot == id {
-        draw_rect_with(platform,
-                       x,
-                       raised_y,
-                       CARD_WIDTH,
-                       CARD_HEIGHT,
-                       ["┌", "─", "╖", "│", "║", "╘", "═", "╝"]);
-        draw_card_value(platform, x + 1, raised_y + 1, card);
+        Some(["┌", "─", "╖", "│", "║", "╘", "═", "╝"])
     } else {
-        draw_card(platform, x, y, card);
+        None
+    };
+
+    //a raised card goes over the rest of the hand rather than replacing it.
+    match raised_edges {
+        Some(edges) => {
+            layers::with_layer(platform, Layer::Raised, || {
+                layers::fill(platform, Rect::from_values(x, drawn_y, w, h), palette.card_face);
+                theme::with_colors(platform,
+                                   palette.highlight,
+                                   palette.card_face,
+                                   || draw_rect_with(platform, x, drawn_y, w, h, edges));
+                card_face::draw(platform, palette, x, drawn_y, w, h, card);
+            });
+
+            layers::with_layer(platform, Layer::Tooltip, || {
+                let caption_y = drawn_y - 1;
+
+                layers::fill(platform, Rect::from_values(x, caption_y, w, 1), palette.card_face);
+                theme::with_colors(platform,
+                                   palette.highlight,
+                                   palette.card_face,
+                                   || print_centered_line(platform, x, caption_y, w, 1, caption));
+            })
+        }
+        None => {
+            layers::with_layer(platform,
+                               Layer::Cards,
+                               || draw_card_sized(platform, palette, x, y, w, h, card))
+        }
     }
 
     return result;
@@ -702,59 +1516,75 @@ fn do_raised_card_button(platform: &Platform,
 
 fn do_card_button(platform: &Platform,
                   context: &mut UIContext,
+                  palette: &Palette,
                   x: i32,
                   y: i32,
+                  size: Size,
                   card: &Card,
                   left_mouse_pressed: bool,
-                  left_mouse_released: bool,
-                  id: UiId)
+                  left_mouse_released: bool)
                   -> bool {
     let spec = ButtonSpec {
         x,
         y,
-        w: CARD_WIDTH,
-        h: CARD_HEIGHT,
+        w: size.width,
+        h: size.height,
         text: String::new(),
-        id,
+        id: context.new_id(),
     };
 
-    let result = do_button(platform,
-                           context,
-                           &spec,
-                           left_mouse_pressed,
-                           left_mouse_released);
+    layers::with_layer(platform, Layer::Cards, || {
+        layers::fill(platform, Rect::from_values(x, y, size.width, size.height), palette.card_face);
+
+        let result = theme::with_colors(platform, palette.card_edge, palette.card_face, || {
+            do_button(platform,
+                      context,
+                      palette,
+                      &spec,
+                      left_mouse_pressed,
+                      left_mouse_released)
+        });
 
-    draw_card_value(platform, x + 1, y + 1, card);
+        card_face::draw(platform, palette, x, y, size.width, size.height, card);
 
-    result
+        result
+    })
 }
 
 fn do_card_back_button(platform: &Platform,
                        context: &mut UIContext,
+                       palette: &Palette,
                        x: i32,
                        y: i32,
+                       size: Size,
                        left_mouse_pressed: bool,
-                       left_mouse_released: bool,
-                       id: UiId)
+                       left_mouse_released: bool)
                        -> bool {
     let spec = ButtonSpec {
         x,
         y,
-        w: CARD_WIDTH,
-        h: CARD_HEIGHT,
+        w: size.width,
+        h: size.height,
         text: String::new(),
-        id,
+        id: context.new_id(),
     };
 
-    let result = do_button(platform,
-                           context,
-                           &spec,
-                           left_mouse_pressed,
-                           left_mouse_released);
+    layers::with_layer(platform, Layer::Cards, || {
+        layers::fill(platform, Rect::from_values(x, y, size.width, size.height), palette.card_face);
+
+        let result = theme::with_colors(platform, palette.card_edge, palette.card_face, || {
+            do_button(platform,
+                      context,
+                      palette,
+                      &spec,
+                      left_mouse_pressed,
+                      left_mouse_released)
+        });
 
-    draw_card_back_design(platform, x, y);
+        draw_card_back_design_sized(platform, palette, x, y, size.width, size.height);
 
-    result
+        result
+    })
 }
 
 fn cross_mode_event_handling(platform: &Platform, state: &mut State, event: &Event) {
@@ -767,6 +1597,28 @@ fn cross_mode_event_handling(platform: &Platform, state: &mut State, event: &Eve
             println!("reset");
             *state = new_state((platform.size)());
         }
+        Event::KeyPressed {
+            key: KeyCode::F1,
+            ctrl: _,
+            shift: _,
+        } |
+        Event::KeyPressed {
+            key: KeyCode::Slash,
+            ctrl: _,
+            shift: true,
+        } => {
+            if let Some(Dialog::Rules(_)) = state.dialog {
+                state.dialog = None;
+            } else {
+                open_dialog(state, Dialog::Rules(0));
+            }
+        }
+        Event::Resize { .. } => {
+            //`Layout` reflows everything, so whatever was under the mouse
+            //probably isn't anymore.
+            state.ui_context.set_not_hot();
+            state.ui_context.set_not_active();
+        }
         _ => (),
     }
 }
@@ -785,46 +1637,36 @@ pub struct ButtonSpec {
 //called multiple times per frame (once for each click).
 fn do_button(platform: &Platform,
              context: &mut UIContext,
+             palette: &Palette,
              spec: &ButtonSpec,
              left_mouse_pressed: bool,
              left_mouse_released: bool)
              -> bool {
-    let mut result = false;
-
     let mouse_pos = (platform.mouse_position)();
     let inside = inside_rect(mouse_pos, spec.x, spec.y, spec.w, spec.h);
     let id = spec.id;
 
-    if context.active == id {
-        if left_mouse_released {
-            result = context.hot == id && inside;
-
-            context.set_not_active();
-        }
-    } else if context.hot == id {
-        if left_mouse_pressed {
-            context.set_active(id);
-        }
-    }
-
-    if inside {
-        context.set_next_hot(id);
-    }
+    let result = ui::clickable(context, id, inside, left_mouse_pressed, left_mouse_released);
 
+    //only the foreground changes, so card buttons keep their face colour.
     if context.active == id && (platform.key_pressed)(KeyCode::MouseLeft) {
-        draw_rect_with(platform,
-                       spec.x,
-                       spec.y,
-                       spec.w,
-                       spec.h,
-                       ["╔", "═", "╕", "║", "│", "╙", "─", "┘"]);
+        theme::with_foreground(platform, palette.highlight, || {
+            draw_rect_with(platform,
+                           spec.x,
+                           spec.y,
+                           spec.w,
+                           spec.h,
+                           ["╔", "═", "╕", "║", "│", "╙", "─", "┘"])
+        });
     } else if context.hot == id {
-        draw_rect_with(platform,
-                       spec.x,
-                       spec.y,
-                       spec.w,
-                       spec.h,
-                       ["┌", "─", "╖", "│", "║", "╘", "═", "╝"]);
+        theme::with_foreground(platform, palette.highlight, || {
+            draw_rect_with(platform,
+                           spec.x,
+                           spec.y,
+                           spec.w,
+                           spec.h,
+                           ["┌", "─", "╖", "│", "║", "╘", "═", "╝"])
+        });
     } else {
         draw_rect(platform, spec.x, spec.y, spec.w, spec.h);
     }
@@ -838,6 +1680,12 @@ pub fn inside_rect(point: Point, x: i32, y: i32, w: i32, h: i32) -> bool {
     x <= point.x && y <= point.y && point.x < x + w && point.y < y + h
 }
 
+fn print_truncated(platform: &Platform, x: i32, y: i32, width: usize, text: &str) {
+    let truncated: String = text.chars().take(width).collect();
+
+    (platform.print_xy)(x, y, &truncated);
+}
+
 fn print_centered_line(platform: &Platform, x: i32, y: i32, w: i32, h: i32, text: &str) {
     let char_count = text.chars().count();
 
@@ -891,3 +1739,154 @@ fn draw_rect_with(platform: &Platform, x: i32, y: i32, w: i32, h: i32, edges: [&
     }
     (platform.print_xy)(right, bottom, edges[7]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::Suit::*;
+    use common::Value::*;
+
+    fn hand(cards: &[(Value, Suit)]) -> HandEnum {
+        Hand(cards.iter().map(|&(value, suit)| Card { suit, value }).collect())
+    }
+
+    fn thirty_one_hand() -> HandEnum {
+        hand(&[(Ace, Spades), (King, Spades), (Queen, Spades)])
+    }
+    fn twenty_five() -> HandEnum {
+        hand(&[(King, Clubs), (Queen, Clubs), (Five, Clubs)])
+    }
+    fn ten() -> HandEnum {
+        hand(&[(Ten, Hearts), (Two, Spades), (Three, Diamonds)])
+    }
+
+    //You, then each cpu, holding `hands` in that order, with nothing but low
+    //cards to draw so no one can stumble into a better hand.
+    fn state(rules: Ruleset, hands: Vec<HandEnum>) -> State {
+        let settings = Settings {
+            opponents: Some(hands.len() - 1),
+            rules,
+            ..Settings::new()
+        };
+        let seed: &[_] = &[42];
+
+        let mut state = make_state(Size::new(80, 30),
+                                   false,
+                                   Mode::Casual,
+                                   settings,
+                                   Statistics::new(),
+                                   None,
+                                   SeedableRng::from_seed(seed));
+
+        let mut hands = hands.into_iter();
+        state.player = hands.next().unwrap();
+        state.cpu_players = hands.collect();
+        state.deck = vec![Card { suit: Clubs, value: Two }; 10];
+        state.pile = vec![Card { suit: Diamonds, value: Two }];
+
+        state
+    }
+
+    fn winner(turn: &Turn) -> Option<Participant> {
+        match *turn {
+            Resolution(Some(Winner(ref winner))) => Some(winner.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn a_31_on_the_deal_ends_it_before_anyone_plays() {
+        let rules = Ruleset { blitz_on_deal: true, ..Ruleset::new() };
+        let mut state = state(rules, vec![ten(), thirty_one_hand(), twenty_five()]);
+
+        blitz_on_deal(&mut state);
+
+        assert_eq!(winner(&state.turn), Some(Cpu(0)));
+    }
+
+    #[test]
+    fn a_31_on_the_deal_waits_without_the_rule() {
+        let mut state = state(Ruleset::new(), vec![ten(), thirty_one_hand(), twenty_five()]);
+
+        blitz_on_deal(&mut state);
+
+        assert_eq!(winner(&state.turn), None);
+    }
+
+    #[test]
+    fn a_31_made_later_is_not_a_blitz_on_the_deal() {
+        let rules = Ruleset { blitz_on_deal: true, ..Ruleset::new() };
+        let mut state = state(rules, vec![ten(), thirty_one_hand(), twenty_five()]);
+        state.turn_count = 1;
+
+        blitz_on_deal(&mut state);
+
+        assert_eq!(winner(&state.turn), None);
+    }
+
+    #[test]
+    fn a_hidden_31_is_only_revealed_by_a_knock() {
+        let rules = Ruleset {
+            hidden_31: true,
+            knock_on_first_round: false,
+            ..Ruleset::new()
+        };
+        let mut state = state(rules, vec![ten(), ten(), thirty_one_hand()]);
+
+        //no one can knock yet, so Cpu 0 just plays and the 31 stays hidden.
+        assert!(take_cpu_turn(&mut state, 0, None).is_none());
+        assert_eq!(revealed_31(&state), Some(Cpu(1)));
+
+        state.round = 1;
+        state.cpu_players[0] = twenty_five();
+
+        //now Cpu 0 knocks, which brings it out.
+        let ending = take_cpu_turn(&mut state, 0, None);
+        match ending {
+            Some(Winner(ref winner)) => assert_eq!(*winner, Cpu(1)),
+            _ => panic!("the knock should have revealed the 31"),
+        }
+
+        let outcome = resolution::resolve(&state, &ending);
+        assert_eq!(outcome.winners, vec![Cpu(1)]);
+        assert_eq!(outcome.thirty_one, Some(Cpu(1)));
+    }
+
+    #[test]
+    fn a_31_is_not_hidden_without_the_rule() {
+        let state = state(Ruleset::new(), vec![ten(), ten(), thirty_one_hand()]);
+
+        //without the rule a 31 ends the deal as soon as it's made, so a knock
+        //never has one to bring out.
+        assert_eq!(revealed_31(&state), None);
+    }
+    #[test]
+    fn the_tutorial_cpu_takes_your_discard_at_any_difficulty() {
+        for &difficulty in Difficulty::all_values().iter() {
+            let settings = Settings { difficulty, ..Settings::new() };
+            let seed: &[_] = &[42];
+            let mut state = make_state(Size::new(80, 30),
+                                       false,
+                                       Mode::Tutorial(0, false),
+                                       settings,
+                                       Statistics::new(),
+                                       None,
+                                       SeedableRng::from_seed(seed));
+
+            //the first two steps: take the A♥ off the pile and put the 3♣ there.
+            let taken = state.pile.pop().unwrap();
+            let discarded = state.player.swap(2, taken);
+            state.pile.push(discarded);
+
+            take_cpu_turn(&mut state, 0, None);
+
+            let three_of_clubs = Card { suit: Clubs, value: Three };
+            assert!(state.cpu_players[0].cards().contains(&three_of_clubs),
+                    "on {}, the cpu did: {}",
+                    difficulty,
+                    state.cpu_last_actions[0]);
+        }
+    }
+}
+
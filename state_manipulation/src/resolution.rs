@@ -0,0 +1,303 @@
+use common::*;
+use common::Participant::*;
+use common::KnockerOrWinner::*;
+
+//How a deal came out, for showing to the player and for keeping score.
+pub struct Outcome {
+    //everyone still playing, in seat order
+    pub scores: Vec<(Participant, Score)>,
+    pub winners: Vec<Participant>,
+    //who lost, and how many lives it costs them
+    pub losers: Vec<(Participant, u32)>,
+    //whoever won with 31, if anyone did
+    pub thirty_one: Option<Participant>,
+}
+
+pub fn hand_of<'a>(state: &'a State, participant: &Participant) -> &'a HandEnum {
+    match *participant {
+        Player => &state.player,
+        Cpu(i) => &state.cpu_players[i],
+    }
+}
+
+//everyone who's still playing, which is everyone outside of a match.
+pub fn still_in(state: &State) -> Vec<Participant> {
+    let everyone = std::iter::once(Player).chain((0..state.cpu_players.len()).map(Cpu));
+
+    everyone.filter(|p| !state.mode.is_out(p)).collect()
+}
+
+//The first one still playing who's holding 31.
+pub fn thirty_one(state: &State) -> Option<Participant> {
+    still_in(state)
+        .into_iter()
+        .find(|p| hand_of(state, p).is_31(&state.rules))
+}
+
+//Settles the deal, given how it ended, under `state.rules`.
+//
+//A 31 wins outright, even one that turned up after a knock, and with
+//`Ruleset::blitz_everyone_loses` everyone else loses. Otherwise the best hands
+//win, and the worst of the rest lose. Ties involving the knocker go the way
+//`Ruleset::knocker_ties` says, and a knocker who ends up losing pays
+//`Ruleset::knocker_penalty`.
+pub fn resolve(state: &State, ending: &Option<KnockerOrWinner>) -> Outcome {
+    let ending = match *ending {
+        Some(Knocker(_)) => thirty_one(state).map(Winner).or(ending.clone()),
+        _ => ending.clone(),
+    };
+    let thirty_one = match ending {
+        Some(Winner(ref winner)) => Some(winner.clone()),
+        _ => None,
+    };
+
+    let scores: Vec<(Participant, Score)> = still_in(state)
+        .into_iter()
+        .map(|p| {
+                 let score = hand_of(state, &p).score(&state.rules);
+                 (p, score)
+             })
+        .collect();
+
+    let knocker = match ending {
+        Some(Knocker(ref knocker)) => Some(knocker),
+        _ => None,
+    };
+    let ties = state.rules.knocker_ties;
+
+    let winners = match ending {
+        Some(Winner(ref winner)) => vec![winner.clone()],
+        _ => {
+            let best = scores.iter().map(|&(_, ref score)| score).max();
+            let tied = scores
+                .iter()
+                .filter(|&&(_, ref score)| Some(score) == best)
+                .map(|&(ref p, _)| p.clone())
+                .collect();
+
+            break_tie(tied, knocker, ties, true)
+        }
+    };
+
+    let rest: Vec<&(Participant, Score)> = scores
+        .iter()
+        .filter(|&&(ref p, _)| !winners.contains(p))
+        .collect();
+    let worst = rest.iter().map(|&&(_, ref score)| score).min();
+    let tied = rest.iter()
+        .filter(|&&&(_, ref score)| Some(score) == worst)
+        .map(|&&(ref p, _)| p.clone())
+        .collect();
+
+    let losing = if thirty_one.is_some() && state.rules.blitz_everyone_loses {
+        rest.iter().map(|&&(ref p, _)| p.clone()).collect()
+    } else {
+        break_tie(tied, knocker, ties, false)
+    };
+
+    let losers = losing
+        .into_iter()
+        .map(|p| {
+                 let lives = if Some(&p) == knocker {
+                     state.rules.knocker_penalty
+                 } else {
+                     1
+                 };
+
+                 (p, lives)
+             })
+        .collect();
+
+    Outcome {
+        scores,
+        winners,
+        losers,
+        thirty_one,
+    }
+}
+
+//Narrows down the people tied for the best hand, if `for_best`, or the worst.
+fn break_tie(tied: Vec<Participant>,
+             knocker: Option<&Participant>,
+             ties: KnockerTies,
+             for_best: bool)
+             -> Vec<Participant> {
+    let knocker = match knocker {
+        Some(knocker) if tied.len() > 1 && tied.contains(knocker) => knocker,
+        _ => return tied,
+    };
+
+    let knocker_comes_out_ahead = match ties {
+        KnockerTies::Shared => return tied,
+        KnockerTies::AgainstKnocker => false,
+        KnockerTies::ForKnocker => true,
+    };
+
+    //at the top, coming out ahead means being the one left in. At the
+    //bottom, it means being the one taken out.
+    if knocker_comes_out_ahead == for_best {
+        vec![knocker.clone()]
+    } else {
+        tied.into_iter().filter(|p| p != knocker).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::Suit::*;
+    use common::Value::*;
+
+    use rand::SeedableRng;
+
+    use make_state;
+
+    fn hand(cards: &[(Value, Suit)]) -> HandEnum {
+        HandEnum::Hand(cards.iter().map(|&(value, suit)| Card { suit, value }).collect())
+    }
+
+    fn thirty_one_hand() -> HandEnum {
+        hand(&[(Ace, Spades), (King, Spades), (Queen, Spades)])
+    }
+    fn twenty_five() -> HandEnum {
+        hand(&[(King, Clubs), (Queen, Clubs), (Five, Clubs)])
+    }
+    fn twenty() -> HandEnum {
+        hand(&[(King, Hearts), (Queen, Hearts), (Two, Clubs)])
+    }
+    fn ten() -> HandEnum {
+        hand(&[(Ten, Hearts), (Two, Spades), (Three, Diamonds)])
+    }
+
+    //You, then each cpu, holding `hands` in that order.
+    fn state(rules: Ruleset, hands: Vec<HandEnum>) -> State {
+        let settings = Settings {
+            opponents: Some(hands.len() - 1),
+            rules,
+            ..Settings::new()
+        };
+        let seed: &[_] = &[42];
+
+        let mut state = make_state(Size::new(80, 30),
+                                   false,
+                                   Mode::Casual,
+                                   settings,
+                                   Statistics::new(),
+                                   None,
+                                   SeedableRng::from_seed(seed));
+
+        let mut hands = hands.into_iter();
+        state.player = hands.next().unwrap();
+        state.cpu_players = hands.collect();
+
+        state
+    }
+
+    fn ties(knocker_ties: KnockerTies) -> Ruleset {
+        Ruleset { knocker_ties, ..Ruleset::new() }
+    }
+
+    fn you_knock() -> Option<KnockerOrWinner> {
+        Some(Knocker(Player))
+    }
+
+    #[test]
+    fn a_tie_for_the_best_hand_is_shared() {
+        let state = state(ties(KnockerTies::Shared), vec![twenty_five(), twenty_five(), ten()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Player, Cpu(0)]);
+        assert_eq!(outcome.losers, vec![(Cpu(1), 1)]);
+    }
+
+    #[test]
+    fn a_tie_for_the_worst_hand_is_shared() {
+        let state = state(ties(KnockerTies::Shared), vec![ten(), ten(), twenty_five()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Cpu(1)]);
+        assert_eq!(outcome.losers, vec![(Player, 2), (Cpu(0), 1)]);
+    }
+
+    #[test]
+    fn the_knocker_loses_a_tie_for_the_best_hand_against_the_knocker() {
+        let state = state(ties(KnockerTies::AgainstKnocker),
+                          vec![twenty_five(), twenty_five(), ten()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Cpu(0)]);
+        assert_eq!(outcome.losers, vec![(Cpu(1), 1)]);
+    }
+
+    #[test]
+    fn the_knocker_loses_a_tie_for_the_worst_hand_against_the_knocker() {
+        let state = state(ties(KnockerTies::AgainstKnocker), vec![ten(), ten(), twenty_five()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Cpu(1)]);
+        assert_eq!(outcome.losers, vec![(Player, 2)]);
+    }
+
+    #[test]
+    fn the_knocker_wins_a_tie_for_the_best_hand_for_the_knocker() {
+        let state = state(ties(KnockerTies::ForKnocker), vec![twenty_five(), twenty_five(), ten()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Player]);
+        assert_eq!(outcome.losers, vec![(Cpu(1), 1)]);
+    }
+
+    #[test]
+    fn the_knocker_wins_a_tie_for_the_worst_hand_for_the_knocker() {
+        let state = state(ties(KnockerTies::ForKnocker), vec![ten(), ten(), twenty_five()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.winners, vec![Cpu(1)]);
+        assert_eq!(outcome.losers, vec![(Cpu(0), 1)]);
+    }
+
+    #[test]
+    fn a_knocker_with_the_worst_hand_pays_the_penalty() {
+        for knocker_penalty in 1..MAX_KNOCKER_PENALTY + 1 {
+            let rules = Ruleset { knocker_penalty, ..Ruleset::new() };
+            let state = state(rules, vec![ten(), twenty(), twenty_five()]);
+
+            let outcome = resolve(&state, &you_knock());
+            assert_eq!(outcome.losers, vec![(Player, knocker_penalty)]);
+
+            //anyone else with the worst hand only loses the one life.
+            let outcome = resolve(&state, &Some(Knocker(Cpu(1))));
+            assert_eq!(outcome.losers, vec![(Player, 1)]);
+        }
+    }
+
+    #[test]
+    fn a_31_after_a_knock_wins_outright() {
+        let state = state(Ruleset::new(), vec![twenty_five(), thirty_one_hand(), ten()]);
+        let outcome = resolve(&state, &you_knock());
+
+        assert_eq!(outcome.thirty_one, Some(Cpu(0)));
+        assert_eq!(outcome.winners, vec![Cpu(0)]);
+        //the knocker isn't the worst hand, so there's no penalty to pay.
+        assert_eq!(outcome.losers, vec![(Cpu(1), 1)]);
+    }
+    #[test]
+    fn with_everyone_else_loses_every_other_player_loses_a_life() {
+        let rules = Ruleset { blitz_everyone_loses: true, ..Ruleset::new() };
+        let state = state(rules, vec![twenty_five(), thirty_one_hand(), ten(), twenty()]);
+        let outcome = resolve(&state, &Some(Winner(Cpu(0))));
+
+        assert_eq!(outcome.winners, vec![Cpu(0)]);
+        assert_eq!(outcome.losers, vec![(Player, 1), (Cpu(1), 1), (Cpu(2), 1)]);
+    }
+
+    #[test]
+    fn without_everyone_else_loses_only_the_worst_hand_loses_to_a_31() {
+        let state = state(Ruleset::new(), vec![twenty_five(), thirty_one_hand(), ten(), twenty()]);
+        let outcome = resolve(&state, &Some(Winner(Cpu(0))));
+
+        assert_eq!(outcome.winners, vec![Cpu(0)]);
+        assert_eq!(outcome.losers, vec![(Cpu(1), 1)]);
+    }
+}
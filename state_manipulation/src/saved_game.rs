@@ -0,0 +1,32 @@
+use common::*;
+use common::save;
+
+const SAVED_GAME_PATH: &'static str = "saved_game.t31";
+
+pub fn exists() -> bool {
+    std::path::Path::new(SAVED_GAME_PATH).exists()
+}
+
+pub fn save(state: &mut State) {
+    if let Err(error) = std::fs::write(SAVED_GAME_PATH, save::save_state(state)) {
+        println!("Could not write {}: {}", SAVED_GAME_PATH, error);
+    }
+}
+
+pub fn load() -> Option<State> {
+    let bytes = match std::fs::read(SAVED_GAME_PATH) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            println!("Could not read {}: {}", SAVED_GAME_PATH, error);
+            return None;
+        }
+    };
+
+    match save::load_state(&bytes) {
+        Ok(state) => Some(state),
+        Err(error) => {
+            println!("Could not load {}: {}", SAVED_GAME_PATH, error);
+            None
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use common::*;
+use common::odds;
+
+use card_face;
+use layout::Layout;
+
+use super::print_truncated;
+
+//how far ahead the odds of 31 look
+const LOOKAHEAD: u32 = 2;
+
+//Just the number, since there isn't room for "points" everywhere.
+pub fn points(score: &Score) -> String {
+    match *score {
+        Score::Simple(x) => x.to_string(),
+        Score::ThirtyAndAHalf => "30½".to_string(),
+    }
+}
+
+//Works out the odds for `draw` once per turn, rather than every frame.
+pub fn update(state: &mut State) {
+    if let Turn::PlayerTurn(_) = state.turn {
+        let up_to_date = match state.odds {
+            Some(ref odds) => odds.is_for(&state.player, &state.pile),
+            None => false,
+        };
+
+        if !up_to_date {
+            state.odds = Some(odds::Summary::new(&state.player,
+                                                 &state.pile,
+                                                 LOOKAHEAD,
+                                                 &state.rules));
+        }
+    }
+}
+
+//Your score, what each suit adds up to, what taking from the pile or the deck
+//is likely to get you, and while you're choosing a discard, what each choice
+//would leave you with.
+pub fn draw(platform: &Platform, state: &State, layout: &Layout) {
+    let panel = layout.score_panel;
+    let rules = &state.rules;
+    let mut lines = Vec::new();
+
+    lines.push(format!("Score: {}", points(&state.player.score(rules))));
+
+    //with any jokers counted the way the score counts them
+    let played = jokers_played(state.player.cards(), rules);
+    let totals: Vec<String> = suit_totals(&played, rules)
+        .iter()
+        .map(|&(suit, total)| format!("{}{}", suit, total))
+        .collect();
+
+    lines.push(totals.join(" "));
+
+    if let (&Turn::PlayerTurn(_), &Some(ref odds)) = (&state.turn, &state.odds) {
+        lines.push(String::new());
+
+        if let Some(from_pile) = odds.from_pile {
+            lines.push(format!("Pile → {}", from_pile));
+        }
+
+        lines.push(format!("Deck → {:.1}", odds.from_deck));
+        lines.push(format!("{} in {}: {:.0}%",
+                           rules.best_score(),
+                           odds.draws,
+                           odds.chance_of_best * 100.0));
+    }
+
+    if let Turn::PlayerSelected(ref selected, _) = state.turn {
+        lines.push("If you discard:".to_string());
+
+        for (i, card) in state.player.cards().iter().enumerate() {
+            let score = state.player.with_swapped(i, selected).score(rules);
+
+            lines.push(format!("{}{} → {}",
+                               card.value,
+                               card_face::suit_text(card),
+                               points(&score)));
+        }
+
+        lines.push(format!("{}{} → {}",
+                           selected.value,
+                           card_face::suit_text(selected),
+                           points(&state.player.score(rules))));
+    }
+
+    //the biggest hand just fits, but anything past the bottom of the panel
+    //would be off the bottom of the window.
+    for (i, line) in lines.iter().take(panel.size.height as usize).enumerate() {
+        print_truncated(platform,
+                        panel.top_left.x,
+                        panel.top_left.y + i as i32,
+                        panel.size.width as usize,
+                        line);
+    }
+}
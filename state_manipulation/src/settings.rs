@@ -0,0 +1,400 @@
+use common::*;
+use common::save;
+
+use layout::Layout;
+use theme::Palette;
+use ui::{self, Input};
+use ui::MenuItem::*;
+
+const SETTINGS_PATH: &'static str = "settings.t31";
+
+//A missing or unreadable file just means the defaults.
+pub fn load() -> Settings {
+    match std::fs::read(SETTINGS_PATH) {
+        Ok(bytes) => {
+            save::load_settings(&bytes).unwrap_or_else(|error| {
+                println!("Could not read {} ({}), using the defaults.", SETTINGS_PATH, error);
+                Settings::new()
+            })
+        }
+        Err(_) => Settings::new(),
+    }
+}
+
+pub fn save(settings: &Settings) {
+    if let Err(error) = std::fs::write(SETTINGS_PATH, save::save_settings(settings)) {
+        println!("Could not write {}: {}", SETTINGS_PATH, error);
+    }
+}
+
+const MENU_WIDTH: i32 = 46;
+const CARD_POINTS_WIDTH: i32 = 24;
+
+//what `CardPoints` that aren't one of the presets show up as
+const CUSTOM: &'static str = "Custom";
+
+const NOTE: &'static str = "Opponents and rules apply from the next deal.";
+
+//What the player did with a settings menu this frame.
+pub enum Outcome {
+    Open,
+    Done,
+    //they want to change the house rules, which have a menu of their own
+    HouseRules,
+    //they want to choose what each card is worth themselves
+    CustomCardPoints,
+}
+
+//A line in one of the settings menus, so what each line does can't come apart
+//from where it is.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Setting {
+    Theme,
+    Difficulty,
+    RandomOpponents,
+    Opponents,
+    Tracker,
+    HouseRules,
+    KnockOnFirstRound,
+    KnockerTies,
+    KnockerPenalty,
+    BlitzOnDeal,
+    BlitzEveryoneLoses,
+    Hidden31,
+    HandSize,
+    CardsScored,
+    Decks,
+    CardPoints,
+    Jokers,
+    Done,
+}
+
+const GENERAL: [Setting; 7] = [Setting::Theme,
+                               Setting::Difficulty,
+                               Setting::RandomOpponents,
+                               Setting::Opponents,
+                               Setting::Tracker,
+                               Setting::HouseRules,
+                               Setting::Done];
+
+const HOUSE_RULES: [Setting; 12] = [Setting::KnockOnFirstRound,
+                                    Setting::KnockerTies,
+                                    Setting::KnockerPenalty,
+                                    Setting::BlitzOnDeal,
+                                    Setting::BlitzEveryoneLoses,
+                                    Setting::Hidden31,
+                                    Setting::HandSize,
+                                    Setting::CardsScored,
+                                    Setting::Decks,
+                                    Setting::CardPoints,
+                                    Setting::Jokers,
+                                    Setting::Done];
+
+//with no number chosen, the stepper starts from the most a random one can be.
+fn opponents(settings: &Settings) -> usize {
+    settings.opponents.unwrap_or(MAX_RANDOM_OPPONENTS)
+}
+
+impl Setting {
+    fn item(&self, settings: &Settings) -> ui::MenuItem {
+        let rules = &settings.rules;
+
+        match *self {
+            Setting::Theme => Choice("Theme".to_string(), settings.theme.to_string()),
+            Setting::Difficulty => {
+                Choice("Difficulty".to_string(), settings.difficulty.to_string())
+            }
+            Setting::RandomOpponents => {
+                Toggle("Random number of opponents".to_string(),
+                       settings.opponents.is_none())
+            }
+            Setting::Opponents => {
+                Stepper("Opponents".to_string(), opponents(settings) as i32)
+            }
+            Setting::Tracker => Toggle("Card tracker (T)".to_string(), settings.show_tracker),
+            Setting::HouseRules => Button("House rules".to_string()),
+            Setting::KnockOnFirstRound => {
+                Toggle("Knocking on the first round".to_string(),
+                       rules.knock_on_first_round)
+            }
+            Setting::KnockerTies => {
+                Choice("Knocker ties".to_string(), rules.knocker_ties.to_string())
+            }
+            Setting::KnockerPenalty => {
+                Stepper("Lives a losing knocker loses".to_string(),
+                        rules.knocker_penalty as i32)
+            }
+            Setting::BlitzOnDeal => Toggle("31 on the deal wins".to_string(), rules.blitz_on_deal),
+            Setting::BlitzEveryoneLoses => {
+                Toggle("Everyone else loses to a 31".to_string(),
+                       rules.blitz_everyone_loses)
+            }
+            Setting::Hidden31 => {
+                Toggle("31 stays hidden until a knock".to_string(), rules.hidden_31)
+            }
+            Setting::HandSize => Stepper("Cards in a hand".to_string(), rules.hand_size as i32),
+            Setting::CardsScored => {
+                Stepper("Cards of a suit that score".to_string(),
+                        rules.cards_scored as i32)
+            }
+            Setting::Decks => Stepper("Decks".to_string(), rules.decks as i32),
+            Setting::CardPoints => {
+                Choice("Card points".to_string(), rules.card_points.to_string())
+            }
+            Setting::Jokers => Toggle("Jokers".to_string(), rules.jokers),
+            Setting::Done => Button("Done".to_string()),
+        }
+    }
+
+    fn apply(&self, action: ui::MenuAction, settings: &mut Settings) -> Outcome {
+        match *self {
+            Setting::Theme => {
+                settings.theme = ui::cycle(&Theme::all_values(), &settings.theme, action)
+            }
+            Setting::Difficulty => {
+                settings.difficulty = ui::cycle(&Difficulty::all_values(),
+                                                &settings.difficulty,
+                                                action)
+            }
+            Setting::RandomOpponents => {
+                settings.opponents = match settings.opponents {
+                    Some(_) => None,
+                    None => Some(opponents(settings)),
+                }
+            }
+            Setting::Opponents => {
+                let stepped = ui::step(opponents(settings) as i32,
+                                       action,
+                                       1,
+                                       MAX_OPPONENTS as i32);
+
+                settings.opponents = Some(stepped as usize);
+            }
+            Setting::Tracker => settings.show_tracker = !settings.show_tracker,
+            Setting::HouseRules => return Outcome::HouseRules,
+            Setting::KnockOnFirstRound => {
+                settings.rules.knock_on_first_round = !settings.rules.knock_on_first_round
+            }
+            Setting::KnockerTies => {
+                settings.rules.knocker_ties = ui::cycle(&KnockerTies::all_values(),
+                                                        &settings.rules.knocker_ties,
+                                                        action)
+            }
+            Setting::KnockerPenalty => {
+                let stepped = ui::step(settings.rules.knocker_penalty as i32,
+                                       action,
+                                       1,
+                                       MAX_KNOCKER_PENALTY as i32);
+
+                settings.rules.knocker_penalty = stepped as u32;
+            }
+            Setting::BlitzOnDeal => settings.rules.blitz_on_deal = !settings.rules.blitz_on_deal,
+            Setting::BlitzEveryoneLoses => {
+                settings.rules.blitz_everyone_loses = !settings.rules.blitz_everyone_loses
+            }
+            Setting::Hidden31 => settings.rules.hidden_31 = !settings.rules.hidden_31,
+            Setting::HandSize => {
+                let stepped = ui::step(settings.rules.hand_size as i32,
+                                       action,
+                                       MIN_HAND_SIZE as i32,
+                                       MAX_HAND_SIZE as i32);
+
+                settings.rules.hand_size = stepped as usize;
+                //you can't score more cards than you're holding.
+                settings.rules.cards_scored = std::cmp::min(settings.rules.cards_scored,
+                                                            settings.rules.hand_size);
+            }
+            Setting::CardsScored => {
+                let stepped = ui::step(settings.rules.cards_scored as i32,
+                                       action,
+                                       MIN_HAND_SIZE as i32,
+                                       settings.rules.hand_size as i32);
+
+                settings.rules.cards_scored = stepped as usize;
+            }
+            Setting::Decks => {
+                let stepped = ui::step(settings.rules.decks as i32, action, 1, MAX_DECKS as i32);
+
+                settings.rules.decks = stepped as usize;
+            }
+            Setting::CardPoints => {
+                let presets = [CardPoints::ace_high(), CardPoints::ace_low()];
+                let mut choices: Vec<String> = presets.iter().map(|p| p.to_string()).collect();
+                choices.push(CUSTOM.to_string());
+
+                let chosen = ui::cycle(&choices, &settings.rules.card_points.to_string(), action);
+
+                match presets.iter().find(|p| p.to_string() == chosen) {
+                    Some(preset) => settings.rules.card_points = preset.clone(),
+                    //starting from whatever they were
+                    None => return Outcome::CustomCardPoints,
+                }
+            }
+            Setting::Jokers => settings.rules.jokers = !settings.rules.jokers,
+            Setting::Done => return Outcome::Done,
+        }
+
+        save(settings);
+
+        Outcome::Open
+    }
+}
+
+//Big enough for `entries` and the note under them.
+fn dialog_size(entries: &[Setting]) -> Size {
+    let note_lines = ui::wrap(NOTE, MENU_WIDTH as usize).len() as i32;
+
+    Size::new(MENU_WIDTH + 4, entries.len() as i32 + note_lines + 6)
+}
+
+pub fn do_menu(platform: &Platform,
+               context: &mut UIContext,
+               palette: &Palette,
+               layout: &Layout,
+               input: &Input,
+               settings: &mut Settings)
+               -> Outcome {
+    do_settings_dialog(platform,
+                       context,
+                       palette,
+                       layout,
+                       input,
+                       "Settings",
+                       &GENERAL,
+                       settings)
+}
+
+//Opened from `do_menu`, and it's up to the caller to go back there when done.
+pub fn do_house_rules_menu(platform: &Platform,
+                           context: &mut UIContext,
+                           palette: &Palette,
+                           layout: &Layout,
+                           input: &Input,
+                           settings: &mut Settings)
+                           -> Outcome {
+    do_settings_dialog(platform,
+                       context,
+                       palette,
+                       layout,
+                       input,
+                       "House rules",
+                       &HOUSE_RULES,
+                       settings)
+}
+
+fn do_settings_dialog(platform: &Platform,
+                      context: &mut UIContext,
+                      palette: &Palette,
+                      layout: &Layout,
+                      input: &Input,
+                      title: &str,
+                      entries: &[Setting],
+                      settings: &mut Settings)
+                      -> Outcome {
+    let items: Vec<ui::MenuItem> = entries.iter().map(|entry| entry.item(settings)).collect();
+
+    let used = ui::modal_dialog(platform,
+                                context,
+                                palette,
+                                layout,
+                                title,
+                                dialog_size(entries),
+                                |context, rect| {
+        let x = rect.top_left.x + 2;
+        let menu_y = rect.top_left.y + 3;
+
+        ui::label(platform,
+                  x,
+                  menu_y + items.len() as i32 + 1,
+                  MENU_WIDTH,
+                  NOTE);
+
+        ui::menu(platform,
+                 context,
+                 palette,
+                 input,
+                 Point::new(x, menu_y),
+                 MENU_WIDTH,
+                 &items)
+    });
+
+    match used {
+        Some((index, action)) => entries[index].apply(action, settings),
+        None => Outcome::Open,
+    }
+}
+
+//A stepper for what each value is worth. Returns true once the player is done
+//with it.
+pub fn do_card_points_menu(platform: &Platform,
+                           context: &mut UIContext,
+                           palette: &Palette,
+                           layout: &Layout,
+                           input: &Input,
+                           settings: &mut Settings)
+                           -> bool {
+    let values = Value::all_values();
+
+    let mut items: Vec<ui::MenuItem> = values
+        .iter()
+        .map(|&value| {
+                 Stepper(value.to_string(),
+                         settings.rules.card_points.of(value) as i32)
+             })
+        .collect();
+    items.push(Button("Done".to_string()));
+
+    let size = Size::new(CARD_POINTS_WIDTH + 4, items.len() as i32 + 6);
+
+    let used = ui::modal_dialog(platform,
+                                context,
+                                palette,
+                                layout,
+                                "Card points",
+                                size,
+                                |context, rect| {
+        ui::menu(platform,
+                 context,
+                 palette,
+                 input,
+                 Point::new(rect.top_left.x + 2, rect.top_left.y + 3),
+                 CARD_POINTS_WIDTH,
+                 &items)
+    });
+
+    match used {
+        Some((index, action)) if index < values.len() => {
+            let value = values[index];
+            let stepped = ui::step(settings.rules.card_points.of(value) as i32,
+                                   action,
+                                   1,
+                                   MAX_CARD_POINTS as i32);
+
+            settings.rules.card_points.set(value, stepped as u8);
+            save(settings);
+
+            false
+        }
+        Some(_) => true,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use layout::{MIN_HEIGHT, MIN_WIDTH};
+
+    #[test]
+    fn every_settings_menu_fits_the_smallest_window() {
+        for entries in [&GENERAL[..], &HOUSE_RULES[..]].iter() {
+            let size = dialog_size(entries);
+
+            assert!(size.width <= MIN_WIDTH && size.height <= MIN_HEIGHT,
+                    "{:?} needs {}",
+                    entries,
+                    size);
+        }
+    }
+}
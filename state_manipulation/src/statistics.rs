@@ -0,0 +1,107 @@
+use common::*;
+use common::save;
+
+use layout::Layout;
+use theme::Palette;
+use ui::{self, Input};
+use ui::MenuItem::*;
+
+const STATISTICS_PATH: &'static str = "statistics.t31";
+
+//A missing or unreadable file just means starting from nothing.
+pub fn load() -> Statistics {
+    match std::fs::read(STATISTICS_PATH) {
+        Ok(bytes) => {
+            save::load_statistics(&bytes).unwrap_or_else(|error| {
+                println!("Could not read {} ({}), starting over.", STATISTICS_PATH, error);
+                Statistics::new()
+            })
+        }
+        Err(_) => Statistics::new(),
+    }
+}
+
+fn save(statistics: &Statistics) {
+    if let Err(error) = std::fs::write(STATISTICS_PATH, save::save_statistics(statistics)) {
+        println!("Could not write {}: {}", STATISTICS_PATH, error);
+    }
+}
+
+pub fn record_deal(statistics: &mut Statistics, won: bool, thirty_one: bool) {
+    statistics.deals_played += 1;
+    if won {
+        statistics.deals_won += 1;
+    }
+    if thirty_one {
+        statistics.thirty_ones += 1;
+    }
+
+    save(statistics);
+}
+
+pub fn record_match(statistics: &mut Statistics, won: bool) {
+    statistics.matches_played += 1;
+    if won {
+        statistics.matches_won += 1;
+    }
+
+    save(statistics);
+}
+
+fn percentage(part: u32, whole: u32) -> String {
+    if whole == 0 {
+        "-".to_string()
+    } else {
+        format!("{}%", part * 100 / whole)
+    }
+}
+
+const DIALOG_WIDTH: i32 = 32;
+
+//Returns true once the player is done with it.
+pub fn do_dialog(platform: &Platform,
+                 context: &mut UIContext,
+                 palette: &Palette,
+                 layout: &Layout,
+                 input: &Input,
+                 statistics: &Statistics)
+                 -> bool {
+    let lines = [format!("Deals played: {}", statistics.deals_played),
+                 format!("Deals won: {} ({})",
+                         statistics.deals_won,
+                         percentage(statistics.deals_won, statistics.deals_played)),
+                 format!("31s: {}", statistics.thirty_ones),
+                 format!("Matches played: {}", statistics.matches_played),
+                 format!("Matches won: {} ({})",
+                         statistics.matches_won,
+                         percentage(statistics.matches_won, statistics.matches_played))];
+
+    let items = [Button("Close".to_string())];
+    let size = Size::new(DIALOG_WIDTH + 4, lines.len() as i32 + 6);
+
+    let used = ui::modal_dialog(platform,
+                                context,
+                                palette,
+                                layout,
+                                "Statistics",
+                                size,
+                                |context, rect| {
+        let x = rect.top_left.x + 2;
+        let mut y = rect.top_left.y + 3;
+
+        for line in lines.iter() {
+            (platform.print_xy)(x, y, line);
+            y += 1;
+        }
+
+        ui::menu(platform,
+                 context,
+                 palette,
+                 input,
+                 Point::new(x, y + 1),
+                 DIALOG_WIDTH,
+                 &items)
+    });
+
+    used.is_some()
+}
@@ -0,0 +1,125 @@
+use common::*;
+
+pub struct Palette {
+    //the felt everything sits on
+    pub table: Color,
+    pub text: Color,
+    pub card_face: Color,
+    pub card_edge: Color,
+    pub card_back: Color,
+    //the border of whichever button is under the mouse or being clicked
+    pub highlight: Color,
+    clubs: Color,
+    diamonds: Color,
+    hearts: Color,
+    spades: Color,
+}
+
+impl Palette {
+    pub fn suit(&self, suit: Suit) -> Color {
+        match suit {
+            Suit::Clubs => self.clubs,
+            Suit::Diamonds => self.diamonds,
+            Suit::Hearts => self.hearts,
+            Suit::Spades => self.spades,
+        }
+    }
+}
+
+fn rgb(red: u8, green: u8, blue: u8) -> Color {
+    Color {
+        red,
+        green,
+        blue,
+        alpha: 255,
+    }
+}
+
+pub fn palette(theme: Theme) -> Palette {
+    match theme {
+        Theme::Classic => {
+            let red = rgb(190, 0, 0);
+            let black = rgb(20, 20, 20);
+
+            Palette {
+                table: rgb(0, 90, 40),
+                text: rgb(235, 235, 225),
+                card_face: rgb(250, 250, 240),
+                card_edge: rgb(60, 60, 60),
+                card_back: rgb(30, 60, 150),
+                highlight: rgb(230, 120, 0),
+                clubs: black,
+                diamonds: red,
+                hearts: red,
+                spades: black,
+            }
+        }
+        Theme::HighContrast => {
+            let red = rgb(255, 80, 80);
+            let white = rgb(255, 255, 255);
+
+            Palette {
+                table: rgb(0, 0, 0),
+                text: white,
+                card_face: rgb(0, 0, 0),
+                card_edge: white,
+                card_back: rgb(0, 255, 255),
+                highlight: rgb(255, 255, 0),
+                clubs: white,
+                diamonds: red,
+                hearts: red,
+                spades: white,
+            }
+        }
+        //These are from the Okabe-Ito palette, so every suit has its own colour
+        //and they don't depend on telling red from green.
+        Theme::ColorblindSafe => {
+            Palette {
+                table: rgb(35, 45, 60),
+                text: rgb(235, 235, 235),
+                card_face: rgb(250, 250, 250),
+                card_edge: rgb(60, 60, 60),
+                card_back: rgb(0, 114, 178),
+                highlight: rgb(204, 121, 167),
+                clubs: rgb(0, 158, 115),
+                diamonds: rgb(0, 114, 178),
+                hearts: rgb(213, 94, 0),
+                spades: rgb(0, 0, 0),
+            }
+        }
+    }
+}
+
+//Runs `draw` with the given colours, then puts back whatever was set before.
+pub fn with_colors<F, R>(platform: &Platform, fg: Color, bg: Color, draw: F) -> R
+    where F: FnOnce() -> R
+{
+    let (old_fg, old_bg) = (platform.get_colors)();
+
+    (platform.set_colors)(fg, bg);
+    let result = draw();
+    (platform.set_colors)(old_fg, old_bg);
+
+    result
+}
+
+pub fn with_foreground<F, R>(platform: &Platform, fg: Color, draw: F) -> R
+    where F: FnOnce() -> R
+{
+    let old_fg = (platform.get_foreground)();
+
+    (platform.set_foreground)(fg);
+    let result = draw();
+    (platform.set_foreground)(old_fg);
+
+    result
+}
+
+//Clearing on the bottom layer fills with the background colour, so this both
+//paints the felt and sets the colours everything else is drawn with by default.
+pub fn draw_table_background(platform: &Platform, palette: &Palette) {
+    let size = (platform.size)();
+
+    (platform.set_colors)(palette.text, palette.table);
+    (platform.clear)(Some(Rect::from_values(0, 0, size.width, size.height)));
+}
@@ -0,0 +1,157 @@
+use common::*;
+
+use layout::{self, Layout};
+use saved_game;
+use theme;
+use ui::{self, Input, MenuAction};
+use ui::MenuItem::*;
+
+use super::{cross_mode_event_handling, do_dialog, make_state, open_dialog};
+
+const BANNER: [&'static str; 5] = ["╔╦╗╦ ╦╦╦═╗╔╦╗╦ ╦   ╔═╗╔╗╔╔═╗",
+                                   " ║ ╠═╣║╠╦╝ ║ ╚╦╝───║ ║║║║║╣ ",
+                                   " ╩ ╩ ╩╩╩╚═ ╩  ╩    ╚═╝╝╚╝╚═╝",
+                                   "",
+                                   "      ♣  ♦  ♥  ♠"];
+
+const MENU_WIDTH: i32 = 20;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Choice {
+    NewGame,
+    Continue,
+    MatchMode,
+    Practice,
+    Tutorial,
+    Statistics,
+    Rules,
+    Settings,
+    Quit,
+}
+
+impl Choice {
+    fn label(&self) -> &'static str {
+        match *self {
+            Choice::NewGame => "New Game",
+            Choice::Continue => "Continue",
+            Choice::MatchMode => "Match Mode",
+            Choice::Practice => "Practice",
+            Choice::Tutorial => "Tutorial",
+            Choice::Statistics => "Statistics",
+            Choice::Rules => "Rules/Help",
+            Choice::Settings => "Settings",
+            Choice::Quit => "Quit",
+        }
+    }
+}
+
+//returns true if quit requested
+pub fn update_and_render(platform: &Platform, state: &mut State, events: &mut Vec<Event>) -> bool {
+    let mut input = Input::default();
+
+    for event in events {
+        cross_mode_event_handling(platform, state, event);
+        input.record(event);
+
+        match *event {
+            Event::KeyPressed {
+                key: KeyCode::Escape,
+                ctrl: _,
+                shift: _,
+            } if state.dialog.is_some() => state.dialog = None,
+            Event::Close |
+            Event::KeyPressed {
+                key: KeyCode::Escape,
+                ctrl: _,
+                shift: _,
+            } => return true,
+            _ => (),
+        }
+    }
+
+    let layout = Layout::new((platform.size)());
+
+    if layout.too_small {
+        layout::draw_too_small_notice(platform, layout.size);
+
+        return false;
+    }
+
+    state.ui_context.frame_init();
+
+    let palette = theme::palette(state.settings.theme);
+
+    let banner_width = BANNER.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+    let banner_x = (layout.size.width - banner_width) / 2;
+    let mut y = layout.size.height / 8;
+
+    for line in BANNER.iter() {
+        (platform.print_xy)(banner_x, y, line);
+        y += 1;
+    }
+
+    let mut choices = vec![Choice::NewGame];
+    if saved_game::exists() {
+        choices.push(Choice::Continue);
+    }
+    choices.extend_from_slice(&[Choice::MatchMode,
+                                Choice::Practice,
+                                Choice::Tutorial,
+                                Choice::Statistics,
+                                Choice::Rules,
+                                Choice::Settings,
+                                Choice::Quit]);
+
+    let items: Vec<_> = choices
+        .iter()
+        .map(|choice| Button(choice.label().to_string()))
+        .collect();
+
+    let chosen = ui::menu(platform,
+                          &mut state.ui_context,
+                          &palette,
+                          &input,
+                          Point::new((layout.size.width - MENU_WIDTH) / 2, y + 2),
+                          MENU_WIDTH,
+                          &items);
+
+    if let Some((index, MenuAction::Activate)) = chosen {
+        //whatever this opens shouldn't act on the same key press as well.
+        input = Input::default();
+
+        match choices[index] {
+            Choice::NewGame => start(platform, state, Mode::Casual),
+            Choice::Continue => {
+                if let Some(mut saved) = saved_game::load() {
+                    //what the player chose since then wins out.
+                    saved.settings = state.settings.clone();
+                    saved.statistics = state.statistics.clone();
+                    saved.title_screen = false;
+
+                    *state = saved;
+                }
+            }
+            Choice::MatchMode => start(platform, state, Mode::Match(Vec::new())),
+            Choice::Practice => start(platform, state, Mode::Practice),
+            Choice::Tutorial => start(platform, state, Mode::Tutorial(0, false)),
+            Choice::Statistics => open_dialog(state, Dialog::Statistics),
+            Choice::Rules => open_dialog(state, Dialog::Rules(0)),
+            Choice::Settings => open_dialog(state, Dialog::Settings),
+            Choice::Quit => return true,
+        }
+    }
+
+    do_dialog(platform, state, &palette, &layout, &input);
+
+    false
+}
+
+fn start(platform: &Platform, state: &mut State, mode: Mode) {
+    *state = make_state((platform.size)(),
+                        false,
+                        mode,
+                        state.settings.clone(),
+                        state.statistics.clone(),
+                        None,
+                        state.rng);
+}
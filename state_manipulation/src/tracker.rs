@@ -0,0 +1,126 @@
+use common::*;
+
+use layers::{self, Layer};
+use layout::Layout;
+use theme::{self, Palette};
+
+use super::draw_text_box;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Whereabouts {
+    Yours,
+    //everything on the pile was face up when it went there
+    Pile,
+    Unknown,
+}
+
+const CELL_WIDTH: i32 = 3;
+const LEGEND: &'static str = "highlighted: yours  · : not seen";
+
+//Where the player knows a copy of `card` to be, and how many copies haven't
+//turned up, which is only ever more than none with more than one deck. This is
+//worked out from the pile itself rather than remembered, so when the pile is
+//shuffled back into the deck those cards go back to being unknown along with it.
+fn whereabouts(state: &State, card: &Card) -> (Whereabouts, usize) {
+    let (yours, on_pile) = sightings(state, |c| c == card);
+    let unseen = state.rules.decks.saturating_sub(yours + on_pile);
+
+    if yours > 0 {
+        (Whereabouts::Yours, unseen)
+    } else if on_pile > 0 {
+        (Whereabouts::Pile, unseen)
+    } else {
+        (Whereabouts::Unknown, unseen)
+    }
+}
+
+//How many of the cards that `matches` the player is holding, and how many are
+//on the pile.
+fn sightings<F: Fn(&Card) -> bool>(state: &State, matches: F) -> (usize, usize) {
+    let in_hand = state.player.cards().iter().filter(|c| matches(c)).count();
+    let just_taken = match state.turn {
+        Turn::PlayerSelected(ref selected, _) if matches(selected) => 1,
+        _ => 0,
+    };
+    let on_pile = state.pile.iter().filter(|c| matches(c)).count();
+
+    (in_hand + just_taken, on_pile)
+}
+
+//Jokers don't fit in the grid, so they get a line of their own, which has to
+//fit in the grid's width. There are at most six jokers, so each count is one
+//digit.
+fn jokers_line(state: &State) -> String {
+    let (yours, on_pile) = sightings(state, Card::is_joker);
+    let total = state.rules.decks * Card::jokers().len();
+
+    format!("jokers: {} yours, {} on pile, {} not seen",
+            yours,
+            on_pile,
+            total.saturating_sub(yours + on_pile))
+}
+
+//A suit per row and a value per column, in the top right corner.
+pub fn draw(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    if !state.settings.show_tracker {
+        return;
+    }
+
+    let values = Value::all_values();
+    let suits = Suit::all_values();
+
+    let width = 2 + values.len() as i32 * CELL_WIDTH + 4;
+    let joker_rows = if state.rules.jokers { 1 } else { 0 };
+    let height = suits.len() as i32 + joker_rows + 5;
+    let rect = Rect::from_values(std::cmp::max(layout.size.width - width, 0), 0, width, height);
+
+    layers::with_layer(platform, Layer::Dialog, || {
+        draw_text_box(platform, rect, "", palette.table, palette.text);
+
+        let x = rect.top_left.x + 2;
+        let mut y = rect.top_left.y + 1;
+
+        theme::with_colors(platform, palette.text, palette.table, || {
+            for (i, value) in values.iter().enumerate() {
+                (platform.print_xy)(x + 2 + i as i32 * CELL_WIDTH, y, &value.to_string());
+            }
+            y += 1;
+
+            for &suit in suits.iter() {
+                (platform.print_xy)(x, y, &suit.to_string());
+
+                for (i, &value) in values.iter().enumerate() {
+                    let card = Card { suit, value };
+                    let cell_x = x + 2 + i as i32 * CELL_WIDTH;
+
+                    let (whereabouts, unseen) = whereabouts(state, &card);
+                    //a dot after a card that's turned up means there's another
+                    //copy of it still out there.
+                    let text = if unseen > 0 {
+                        format!("{}·", value)
+                    } else {
+                        value.to_string()
+                    };
+
+                    match whereabouts {
+                        Whereabouts::Yours => {
+                            theme::with_foreground(platform, palette.highlight, || {
+                                (platform.print_xy)(cell_x, y, &text)
+                            })
+                        }
+                        Whereabouts::Pile => (platform.print_xy)(cell_x, y, &text),
+                        Whereabouts::Unknown => (platform.print_xy)(cell_x, y, "·"),
+                    }
+                }
+                y += 1;
+            }
+
+            if state.rules.jokers {
+                (platform.print_xy)(x, y, &jokers_line(state));
+                y += 1;
+            }
+
+            (platform.print_xy)(x, y + 1, LEGEND);
+        });
+    });
+}
@@ -0,0 +1,227 @@
+use common::*;
+use common::Participant::*;
+use common::Turn::*;
+
+//Play goes around the table from you, through each cpu in order, and back to
+//you.
+pub fn next_seat(seat: &Participant, cpu_count: usize) -> Participant {
+    match *seat {
+        Player if cpu_count > 0 => Cpu(0),
+        Cpu(i) if i + 1 < cpu_count => Cpu(i + 1),
+        _ => Player,
+    }
+}
+
+//Like `next_seat`, but passing over anyone who is out of the match.
+pub fn next_playing_seat(mode: &Mode, seat: &Participant, cpu_count: usize) -> Participant {
+    let mut next = next_seat(seat, cpu_count);
+
+    //someone is always still in, or the match would be over, but there's no
+    //need to go around more than once to find them.
+    for _ in 0..cpu_count {
+        if !mode.is_out(&next) {
+            break;
+        }
+
+        next = next_seat(&next, cpu_count);
+    }
+
+    next
+}
+
+//whoever sits to the dealer's left.
+pub fn first_to_play(state: &State) -> Participant {
+    next_playing_seat(&state.mode, &state.dealer, state.cpu_players.len())
+}
+
+//The turn in which `seat` starts playing.
+pub fn turn_for(seat: &Participant) -> Turn {
+    match *seat {
+        Player => PlayerTurn(None),
+        Cpu(i) => CpuTurn(None, i),
+    }
+}
+
+//Whose move the game is waiting on, if anyone's.
+pub fn seat_to_play(turn: &Turn) -> Option<Participant> {
+    match *turn {
+        PlayerTurn(_) | PlayerSelected(_, _) => Some(Player),
+        CpuTurn(_, i) => Some(Cpu(i)),
+        CpuSummary(_) | Resolution(_) => None,
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Next {
+    Seat(Participant),
+    Resolution,
+}
+
+//Who goes once `seat` has finished their turn. Play just keeps going around
+//the table until it gets back to whoever knocked, so after a knock everyone
+//else gets exactly one more turn, wherever they sit and whoever went first.
+pub fn after(state: &State, seat: &Participant, knocker: Option<&Participant>) -> Next {
+    let next = next_playing_seat(&state.mode, seat, state.cpu_players.len());
+
+    if Some(&next) == knocker {
+        Next::Resolution
+    } else {
+        Next::Seat(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    use make_state;
+
+    fn index(seat: &Participant) -> usize {
+        match *seat {
+            Player => 0,
+            Cpu(i) => i + 1,
+        }
+    }
+
+    fn seats(cpu_count: usize) -> Vec<Participant> {
+        std::iter::once(Player).chain((0..cpu_count).map(Cpu)).collect()
+    }
+
+    fn state(mode: Mode, cpu_count: usize) -> State {
+        let settings = Settings { opponents: Some(cpu_count), ..Settings::new() };
+        let seed: &[_] = &[42];
+
+        make_state(Size::new(80, 30),
+                   false,
+                   mode,
+                   settings,
+                   Statistics::new(),
+                   None,
+                   SeedableRng::from_seed(seed))
+    }
+
+    //Plays the deal out a seat at a time, with `knocker` knocking on their
+    //`knock_on`th turn, (counting from 0,) and returns how many turns each seat
+    //got, before the knock and after it.
+    fn play_out(state: &State, knocker: &Participant, knock_on: usize) -> (Vec<u32>, Vec<u32>) {
+        let seat_count = state.cpu_players.len() + 1;
+        let mut before = vec![0; seat_count];
+        let mut after_knock = vec![0; seat_count];
+        let mut knocked = false;
+        let mut seat = first_to_play(state);
+
+        //far more turns than any deal that's working properly needs
+        for _ in 0..seat_count * (knock_on + 3) {
+            if knocked {
+                after_knock[index(&seat)] += 1;
+            } else {
+                if seat == *knocker && before[index(&seat)] as usize == knock_on {
+                    knocked = true;
+                }
+
+                before[index(&seat)] += 1;
+            }
+
+            match after(state, &seat, if knocked { Some(knocker) } else { None }) {
+                Next::Seat(next) => seat = next,
+                Next::Resolution => return (before, after_knock),
+            }
+        }
+
+        panic!("the deal never ended, with {:?} knocking", knocker);
+    }
+
+    //Every seat and dealer, for one table.
+    fn check_every_knock(state: &mut State) {
+        let cpu_count = state.cpu_players.len();
+        let playing: Vec<Participant> = seats(cpu_count)
+            .into_iter()
+            .filter(|s| !state.mode.is_out(s))
+            .collect();
+
+        for dealer in playing.iter() {
+            state.dealer = dealer.clone();
+
+            for knocker in playing.iter() {
+                for knock_on in 0..2 {
+                    let (before, after_knock) = play_out(state, knocker, knock_on);
+
+                    for seat in seats(cpu_count) {
+                        let out = state.mode.is_out(&seat);
+                        let expected = if out || seat == *knocker { 0 } else { 1 };
+
+                        assert_eq!(after_knock[index(&seat)],
+                                   expected,
+                                   "{:?} after {:?} knocked on turn {}, with {:?} dealing \
+                                    and {:?}",
+                                   seat,
+                                   knocker,
+                                   knock_on,
+                                   dealer,
+                                   state.mode);
+
+                        if out {
+                            assert_eq!(before[index(&seat)], 0, "{:?} is out", seat);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn everyone_else_gets_one_more_turn_after_a_knock() {
+        for cpu_count in 1..MAX_OPPONENTS + 1 {
+            check_every_knock(&mut state(Mode::Casual, cpu_count));
+        }
+    }
+
+    #[test]
+    fn seats_that_are_out_are_passed_over() {
+        for cpu_count in 1..MAX_OPPONENTS + 1 {
+            //you being out ends the match, but any cpus can be as long as one
+            //of them is left.
+            for out in 0..(1 << cpu_count) - 1 {
+                let lives = seats(cpu_count)
+                    .iter()
+                    .map(|seat| match *seat {
+                             Cpu(i) if out & (1 << i) != 0 => 0,
+                             _ => STARTING_LIVES,
+                         })
+                    .collect();
+
+                check_every_knock(&mut state(Mode::Match(lives), cpu_count));
+            }
+        }
+    }
+
+    #[test]
+    fn no_one_is_skipped_before_a_knock() {
+        for cpu_count in 1..MAX_OPPONENTS + 1 {
+            let mut state = state(Mode::Casual, cpu_count);
+
+            for dealer in seats(cpu_count) {
+                state.dealer = dealer.clone();
+
+                let mut seat = first_to_play(&state);
+                let mut order = vec![seat.clone()];
+
+                for _ in 0..cpu_count {
+                    seat = match after(&state, &seat, None) {
+                        Next::Seat(next) => next,
+                        Next::Resolution => panic!("no one knocked"),
+                    };
+                    order.push(seat.clone());
+                }
+
+                assert_eq!(order[0], next_seat(&dealer, cpu_count));
+                assert_eq!(after(&state, &seat, None), Next::Seat(order[0].clone()));
+
+                order.sort_by_key(index);
+                assert_eq!(order, seats(cpu_count));
+            }
+        }
+    }
+}
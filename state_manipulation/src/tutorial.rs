@@ -0,0 +1,167 @@
+use common::*;
+use common::Suit::*;
+use common::Value::*;
+
+use layers::{self, Layer};
+use layout::Layout;
+use theme::Palette;
+use ui;
+
+use super::draw_text_box;
+
+//The things the tutorial asks the player to do, or stops them from doing.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    TakePile,
+    DrawDeck,
+    //which card in the hand, from the left
+    DiscardFromHand(usize),
+    DiscardSelected,
+    Knock,
+}
+
+struct Step {
+    expected: Action,
+    prompt: &'static str,
+    //shown instead of the prompt after the player tries something else
+    explanation: &'static str,
+}
+
+const STEPS: [Step; 5] =
+    [Step {
+         expected: Action::TakePile,
+         prompt: "Each turn you take a card from the pile or the deck. The A♥ on the pile \
+                  goes with your K♥ and 4♥, so click the pile to take it.",
+         explanation: "Not yet! The A♥ on the pile is the same suit as two of your cards. \
+                       Click the pile to take it.",
+     },
+     Step {
+         expected: Action::DiscardFromHand(2),
+         prompt: "Now put a card back on the pile. The 3♣ doesn't go with your hearts, so \
+                  click it to discard it.",
+         explanation: "Only cards of the same suit add up. Discard the 3♣ and your hearts \
+                       score 25.",
+     },
+     Step {
+         expected: Action::DrawDeck,
+         prompt: "The cpu has had its turn. Nothing on the pile helps you now, so click the \
+                  deck to draw a card no one has seen.",
+         explanation: "The card on the pile won't improve your hand. Draw from the deck \
+                       instead.",
+     },
+     Step {
+         expected: Action::DiscardSelected,
+         prompt: "That card doesn't help either. Click it to put it straight back on the \
+                  pile.",
+         explanation: "Swapping anything would break up your hearts. Discard the card you \
+                       just drew.",
+     },
+     Step {
+         expected: Action::Knock,
+         prompt: "25 is a good hand. Click Knock: everyone else gets one last turn, then \
+                  the best hand wins.",
+         explanation: "Your hand is strong, so don't risk waiting. Click Knock.",
+     }];
+
+pub const FINISHED: &'static str = "That's the tutorial done! Press F1 for the rules any time.";
+
+//Dealt in this order: your hand, the cpu's hand, the first card on the pile,
+//then what gets drawn from the deck.
+const SCRIPT: [(Value, Suit); 9] = [(King, Hearts),
+                                    (Four, Hearts),
+                                    (Three, Clubs),
+                                    (Two, Spades),
+                                    (Five, Diamonds),
+                                    (Eight, Clubs),
+                                    (Ace, Hearts),
+                                    (Two, Diamonds),
+                                    (Nine, Spades)];
+
+pub const OPPONENTS: usize = 1;
+//The script counts on the cpu taking the 3♣ you discard, which it only does
+//when it pays attention to the pile, so it plays at this whatever the settings
+//say.
+pub const DIFFICULTY: Difficulty = Difficulty::Normal;
+
+pub fn deck() -> Vec<Card> {
+    let scripted: Vec<Card> = SCRIPT
+        .iter()
+        .map(|&(value, suit)| Card { value, suit })
+        .collect();
+
+    //the hearts go to the bottom, so no one lucks into 31 partway through.
+    let mut deck: Vec<Card> = Card::all_values()
+        .into_iter()
+        .filter(|card| !scripted.contains(card))
+        .collect();
+    deck.sort_by_key(|card| card.suit != Hearts);
+
+    //cards are dealt off the end.
+    deck.extend(scripted.into_iter().rev());
+
+    deck
+}
+
+pub fn active(state: &State) -> bool {
+    match state.mode {
+        Mode::Tutorial(_, _) => true,
+        _ => false,
+    }
+}
+
+//Whether `action` can go ahead. If it's what the current step asks for, the
+//tutorial moves on to the next one.
+pub fn permit(state: &mut State, action: Action) -> bool {
+    if let Mode::Tutorial(ref mut step, ref mut strayed) = state.mode {
+        match STEPS.get(*step) {
+            Some(current) if current.expected == action => {
+                *step += 1;
+                *strayed = false;
+
+                true
+            }
+            Some(_) => {
+                *strayed = true;
+
+                false
+            }
+            None => true,
+        }
+    } else {
+        true
+    }
+}
+
+//The current step, in the empty space left of the pile, while it's the
+//player's turn.
+pub fn draw_prompt(platform: &Platform, palette: &Palette, state: &State, layout: &Layout) {
+    let (step, strayed) = match state.mode {
+        Mode::Tutorial(step, strayed) => (step, strayed),
+        _ => return,
+    };
+
+    match state.turn {
+        Turn::PlayerTurn(_) |
+        Turn::PlayerSelected(_, _) => {}
+        _ => return,
+    }
+
+    let current = match STEPS.get(step) {
+        Some(current) => current,
+        None => return,
+    };
+
+    let (text, foreground) = if strayed {
+        (current.explanation, palette.highlight)
+    } else {
+        (current.prompt, palette.card_edge)
+    };
+
+    let width = layout.pile.x - layout.log.x - 1;
+    let lines = ui::wrap(text, std::cmp::max(width - 4, 1) as usize);
+    let rect = Rect::from_values(layout.log.x, layout.log.y, width, lines.len() as i32 + 2);
+
+    layers::with_layer(platform, Layer::Raised, || {
+        draw_text_box(platform, rect, &lines.join("\n"), palette.card_face, foreground)
+    });
+}
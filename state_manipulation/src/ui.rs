@@ -0,0 +1,301 @@
+use common::*;
+
+use layers::{self, Layer};
+use layout::Layout;
+use theme::{self, Palette};
+
+use super::{draw_text_box, inside_rect, print_truncated};
+
+//What the widgets need to know about this frame's input.
+#[derive(Clone, Copy, Default)]
+pub struct Input {
+    pub left_mouse_pressed: bool,
+    pub left_mouse_released: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+}
+
+impl Input {
+    pub fn record(&mut self, event: &Event) {
+        match *event {
+            Event::KeyPressed { key: KeyCode::MouseLeft, .. } => self.left_mouse_pressed = true,
+            Event::KeyReleased { key: KeyCode::MouseLeft, .. } => self.left_mouse_released = true,
+            Event::KeyPressed { key: KeyCode::Up, .. } => self.up = true,
+            Event::KeyPressed { key: KeyCode::Down, .. } => self.down = true,
+            Event::KeyPressed { key: KeyCode::Left, .. } => self.left = true,
+            Event::KeyPressed { key: KeyCode::Right, .. } => self.right = true,
+            Event::KeyPressed { key: KeyCode::Enter, .. } |
+            Event::KeyPressed { key: KeyCode::Space, .. } => self.confirm = true,
+            _ => (),
+        }
+    }
+}
+
+//The part every clickable widget shares. Returns true if the mouse was pressed
+//and then released over the widget with this `id`.
+pub fn clickable(context: &mut UIContext,
+                 id: UiId,
+                 inside: bool,
+                 left_mouse_pressed: bool,
+                 left_mouse_released: bool)
+                 -> bool {
+    let inside = inside && !context.blocked();
+    let mut result = false;
+
+    if context.active == id {
+        if left_mouse_released {
+            result = context.hot == id && inside;
+
+            context.set_not_active();
+        }
+    } else if context.hot == id {
+        if left_mouse_pressed {
+            context.set_active(id);
+        }
+    }
+
+    if inside {
+        context.set_next_hot(id);
+    }
+
+    result
+}
+
+pub enum MenuItem {
+    Button(String),
+    Toggle(String, bool),
+    //a label and the name of the current choice
+    Choice(String, String),
+    Stepper(String, i32),
+}
+use self::MenuItem::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuAction {
+    Activate,
+    Previous,
+    Next,
+}
+
+//A column of one line items, which can be clicked on, or moved through with
+//the arrow keys. Returns which item was used, if any, and how.
+pub fn menu(platform: &Platform,
+            context: &mut UIContext,
+            palette: &Palette,
+            input: &Input,
+            position: Point,
+            width: i32,
+            items: &[MenuItem])
+            -> Option<(usize, MenuAction)> {
+    if items.is_empty() {
+        return None;
+    }
+
+    let mouse = (platform.mouse_position)();
+    let mut result = None;
+
+    //the focus is shared with any menu in a dialog over this one.
+    let blocked = context.blocked();
+
+    if !blocked && context.menu_focus >= items.len() {
+        context.menu_focus = items.len() - 1;
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        let id = context.new_id();
+        let y = position.y + i as i32;
+        let inside = inside_rect(mouse, position.x, y, width, 1);
+
+        if clickable(context,
+                     id,
+                     inside,
+                     input.left_mouse_pressed,
+                     input.left_mouse_released) {
+            result = Some((i, match *item {
+                               Button(_) | Toggle(_, _) => MenuAction::Activate,
+                               //the left arrow is the first thing in the value
+                               Choice(_, ref value) => {
+                                   arrow_action(mouse.x, position.x, width, value)
+                               }
+                               Stepper(_, value) => {
+                                   arrow_action(mouse.x, position.x, width, &value.to_string())
+                               }
+                           }));
+        }
+
+        if !blocked && context.hot == id {
+            context.menu_focus = i;
+        }
+    }
+
+    if !blocked {
+        let focus = context.menu_focus;
+        let adjustable = match items[focus] {
+            Choice(_, _) | Stepper(_, _) => true,
+            Button(_) | Toggle(_, _) => false,
+        };
+
+        if input.up {
+            context.menu_focus = (focus + items.len() - 1) % items.len();
+        } else if input.down {
+            context.menu_focus = (focus + 1) % items.len();
+        } else if input.left && adjustable {
+            result = Some((focus, MenuAction::Previous));
+        } else if input.right && adjustable {
+            result = Some((focus, MenuAction::Next));
+        } else if input.confirm {
+            result = Some((focus,
+                           if adjustable {
+                               MenuAction::Next
+                           } else {
+                               MenuAction::Activate
+                           }));
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        let y = position.y + i as i32;
+        let focused = !blocked && i == context.menu_focus;
+
+        let (label, value) = match *item {
+            Button(ref label) => (label.clone(), None),
+            Toggle(ref label, on) => (format!("[{}] {}", if on { "x" } else { " " }, label), None),
+            Choice(ref label, ref value) => (label.clone(), Some(value.clone())),
+            Stepper(ref label, value) => (label.clone(), Some(value.to_string())),
+        };
+
+        let line = format!("{}{}", if focused { "► " } else { "  " }, label);
+
+        let draw_line = || {
+            print_truncated(platform, position.x, y, width as usize, &line);
+
+            if let Some(ref value) = value {
+                let value = format!("< {} >", value);
+                let value_x = position.x + width - value.chars().count() as i32;
+
+                (platform.print_xy)(value_x, y, &value);
+            }
+        };
+
+        if focused {
+            theme::with_foreground(platform, palette.highlight, draw_line);
+        } else {
+            draw_line();
+        }
+    }
+
+    result
+}
+
+//which half of "< value >" was clicked on.
+fn arrow_action(mouse_x: i32, x: i32, width: i32, value: &str) -> MenuAction {
+    let value_width = value.chars().count() as i32 + 4;
+    let middle = x + width - value_width / 2;
+
+    if mouse_x < middle {
+        MenuAction::Previous
+    } else {
+        MenuAction::Next
+    }
+}
+
+//For `Stepper` items.
+pub fn step(value: i32, action: MenuAction, min: i32, max: i32) -> i32 {
+    let stepped = match action {
+        MenuAction::Previous => value - 1,
+        MenuAction::Next | MenuAction::Activate => value + 1,
+    };
+
+    std::cmp::max(min, std::cmp::min(stepped, max))
+}
+
+//For `Choice` items. Wraps around at either end.
+pub fn cycle<T: PartialEq + Clone>(choices: &[T], current: &T, action: MenuAction) -> T {
+    let len = choices.len();
+    let index = choices.iter().position(|c| c == current).unwrap_or(0);
+
+    let new_index = match action {
+        MenuAction::Previous => (index + len - 1) % len,
+        MenuAction::Next | MenuAction::Activate => (index + 1) % len,
+    };
+
+    choices[new_index].clone()
+}
+
+//Splits `text` into lines at most `width` wide, breaking between words where
+//possible.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let line_len = line.chars().count();
+            let word_len = word.chars().count();
+
+            if line_len > 0 && line_len + 1 + word_len > width {
+                lines.push(line);
+                line = String::new();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+
+            //words longer than a whole line get split wherever.
+            while line.chars().count() > width && width > 0 {
+                let rest: String = line.chars().skip(width).collect();
+                lines.push(line.chars().take(width).collect());
+                line = rest;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+//Returns how many lines `text` took up.
+pub fn label(platform: &Platform, x: i32, y: i32, width: i32, text: &str) -> i32 {
+    let lines = wrap(text, std::cmp::max(width, 1) as usize);
+
+    for (i, line) in lines.iter().enumerate() {
+        (platform.print_xy)(x, y + i as i32, line);
+    }
+
+    lines.len() as i32
+}
+
+//Draws a box in the middle of the window with `title` at the top, on top of
+//everything else, and calls `contents` to fill it in. Until a frame goes by
+//without this being called, only widgets inside `contents` respond to input.
+pub fn modal_dialog<F, R>(platform: &Platform,
+                          context: &mut UIContext,
+                          palette: &Palette,
+                          layout: &Layout,
+                          title: &str,
+                          size: Size,
+                          contents: F)
+                          -> R
+    where F: FnOnce(&mut UIContext, Rect) -> R
+{
+    context.begin_modal();
+
+    let result = layers::with_layer(platform, Layer::Dialog, || {
+        let rect = layout.dialog(size);
+
+        draw_text_box(platform, rect, title, palette.table, palette.text);
+
+        contents(context, rect)
+    });
+
+    context.end_modal();
+
+    result
+}